@@ -24,6 +24,24 @@ test.describe('Home Page E2E Tests', () => {
 		await expect(page).toHaveURL('/')
 	})
 
+	test('should render without Content-Security-Policy violations', async ({ page }) => {
+		// Collect violations from the first byte, before any emoji <img> is inserted
+		await page.addInitScript(() => {
+			const violations: string[] = []
+			Object.assign(window, { __cspViolations: violations })
+			document.addEventListener('securitypolicyviolation', (event) => {
+				violations.push(`${event.violatedDirective} ${event.blockedURI}`)
+			})
+		})
+		await page.goto('/')
+		await page.waitForLoadState('networkidle')
+
+		const violations = await page.evaluate(
+			() => (window as unknown as { __cspViolations: string[] }).__cspViolations,
+		)
+		expect(violations).toEqual([])
+	})
+
 	test('should switch themes', async ({ page }) => {
 		const themeSwitcher = page.locator('button[aria-label*="theme"]')
 		await expect(themeSwitcher).toBeVisible()
@@ -0,0 +1,242 @@
+use metrics::gauge;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Breaker state, exported as the `country_breaker_state` gauge (0, 1, 2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls flow normally; consecutive failures are counted.
+    Closed,
+    /// The cool-down has elapsed and a single probe call is allowed through.
+    HalfOpen,
+    /// Calls fail fast until the cool-down elapses.
+    Open,
+}
+
+impl BreakerState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::HalfOpen => "half_open",
+            BreakerState::Open => "open",
+        }
+    }
+
+    fn gauge_value(self) -> f64 {
+        match self {
+            BreakerState::Closed => 0.0,
+            BreakerState::HalfOpen => 1.0,
+            BreakerState::Open => 2.0,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("circuit breaker is open; failing fast")]
+pub struct BreakerOpen;
+
+/// Permission to make one upstream call, handed out by [`CircuitBreaker::try_acquire`].
+///
+/// Report the outcome with [`success`](Self::success) or [`failure`](Self::failure). A permit
+/// dropped without either (the call was cancelled or panicked) records nothing, but frees the
+/// half-open probe slot so a later call can probe instead of failing fast forever.
+#[must_use = "report the outcome with `success` or `failure`"]
+pub struct BreakerPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    /// This permit holds the half-open probe slot and must release it.
+    probe: bool,
+}
+
+impl BreakerPermit<'_> {
+    pub fn success(mut self) {
+        self.probe = false;
+        self.breaker.record_success();
+    }
+
+    pub fn failure(mut self) {
+        self.probe = false;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for BreakerPermit<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            tracing::warn!("Country API probe ended without a result; allowing another probe");
+            let mut inner = self.breaker.inner.lock().expect("breaker lock poisoned");
+            inner.probe_in_flight = false;
+        }
+    }
+}
+
+struct Inner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+/// Circuit breaker guarding calls to the external country API.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        gauge!("country_breaker_state").set(BreakerState::Closed.gauge_value());
+        Self {
+            failure_threshold,
+            cooldown,
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    /// Current state, moving an expired `Open` breaker to `HalfOpen`.
+    pub fn state(&self) -> BreakerState {
+        let mut inner = self.inner.lock().expect("breaker lock poisoned");
+        self.expire_cooldown(&mut inner);
+        inner.state
+    }
+
+    /// Asks permission to call upstream; the call's outcome is reported through the permit.
+    pub fn try_acquire(&self) -> Result<BreakerPermit<'_>, BreakerOpen> {
+        let mut inner = self.inner.lock().expect("breaker lock poisoned");
+        self.expire_cooldown(&mut inner);
+
+        let probe = match inner.state {
+            BreakerState::Closed => false,
+            BreakerState::HalfOpen if !inner.probe_in_flight => {
+                inner.probe_in_flight = true;
+                true
+            }
+            BreakerState::HalfOpen | BreakerState::Open => return Err(BreakerOpen),
+        };
+        Ok(BreakerPermit {
+            breaker: self,
+            probe,
+        })
+    }
+
+    fn record_success(&self) {
+        let mut inner = self.inner.lock().expect("breaker lock poisoned");
+        inner.consecutive_failures = 0;
+        inner.probe_in_flight = false;
+        inner.opened_at = None;
+        if inner.state != BreakerState::Closed {
+            tracing::info!("Country API circuit breaker closed");
+        }
+        Self::transition(&mut inner, BreakerState::Closed);
+    }
+
+    fn record_failure(&self) {
+        let mut inner = self.inner.lock().expect("breaker lock poisoned");
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+
+        let trip = inner.state == BreakerState::HalfOpen
+            || inner.consecutive_failures >= self.failure_threshold;
+        inner.probe_in_flight = false;
+
+        if trip {
+            tracing::warn!(
+                consecutive_failures = inner.consecutive_failures,
+                cooldown_secs = self.cooldown.as_secs(),
+                "Country API circuit breaker opened"
+            );
+            inner.opened_at = Some(Instant::now());
+            Self::transition(&mut inner, BreakerState::Open);
+        }
+    }
+
+    fn expire_cooldown(&self, inner: &mut Inner) {
+        if inner.state == BreakerState::Open
+            && inner
+                .opened_at
+                .is_some_and(|t| t.elapsed() >= self.cooldown)
+        {
+            tracing::info!("Country API circuit breaker half-open; allowing a probe");
+            Self::transition(inner, BreakerState::HalfOpen);
+        }
+    }
+
+    fn transition(inner: &mut Inner, state: BreakerState) {
+        inner.state = state;
+        gauge!("country_breaker_state").set(state.gauge_value());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_threshold_and_fails_fast() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        breaker.try_acquire().unwrap().failure();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        breaker.try_acquire().unwrap().failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.try_acquire().is_err());
+    }
+
+    #[test]
+    fn half_open_allows_one_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.try_acquire().unwrap().failure();
+
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        let probe = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_err());
+
+        // A failed probe re-opens; a successful one closes
+        probe.failure();
+        breaker.try_acquire().unwrap().success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn cancelled_probe_frees_the_slot() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.try_acquire().unwrap().failure();
+
+        // The probing call is cancelled before it reports an outcome
+        let probe = async {
+            let _permit = breaker.try_acquire().unwrap();
+            std::future::pending::<()>().await;
+        };
+        let timeout = tokio::time::timeout(Duration::from_millis(10), probe).await;
+        assert!(timeout.is_err());
+
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        let probe = breaker
+            .try_acquire()
+            .expect("cancelled probe kept the slot");
+        assert!(breaker.try_acquire().is_err());
+        probe.success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn panicked_probe_frees_the_slot() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.try_acquire().unwrap().failure();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _permit = breaker.try_acquire().unwrap();
+            panic!("probe panicked");
+        }));
+        assert!(result.is_err());
+
+        assert!(breaker.try_acquire().is_ok());
+    }
+}
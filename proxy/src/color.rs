@@ -0,0 +1,580 @@
+//! CSS color parsing and conversion between the notations theme consumers can render.
+//!
+//! Colors are held as gamma-encoded sRGB channels that may fall outside `0..=1`, so wide-gamut
+//! `oklch()` values survive an `oklch` round trip and are only clipped when written as hex,
+//! `rgb()` or `hsl()`.
+
+use serde::Deserialize;
+use std::fmt::Write;
+
+/// Output notation requested with `?colorFormat=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorFormat {
+    Hex,
+    Rgb,
+    Oklch,
+    Hsl,
+}
+
+impl ColorFormat {
+    pub const ALL: [ColorFormat; 4] = [
+        ColorFormat::Hex,
+        ColorFormat::Rgb,
+        ColorFormat::Oklch,
+        ColorFormat::Hsl,
+    ];
+}
+
+/// Why a value could not be read as a color.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unrecognized color {0:?}")]
+pub struct ParseColorError(pub String);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub alpha: f64,
+}
+
+/// CSS basic named colors.
+const NAMED: &[(&str, u32)] = &[
+    ("black", 0x000000),
+    ("silver", 0xc0c0c0),
+    ("gray", 0x808080),
+    ("grey", 0x808080),
+    ("white", 0xffffff),
+    ("maroon", 0x800000),
+    ("red", 0xff0000),
+    ("purple", 0x800080),
+    ("fuchsia", 0xff00ff),
+    ("green", 0x008000),
+    ("lime", 0x00ff00),
+    ("olive", 0x808000),
+    ("yellow", 0xffff00),
+    ("navy", 0x000080),
+    ("blue", 0x0000ff),
+    ("teal", 0x008080),
+    ("aqua", 0x00ffff),
+    ("orange", 0xffa500),
+];
+
+impl Color {
+    /// Parses hex, `rgb()`/`rgba()`, `hsl()`/`hsla()`, `oklch()`, basic named colors and
+    /// `transparent`. Surrounding quotes are ignored.
+    pub fn parse(value: &str) -> Result<Self, ParseColorError> {
+        let err = || ParseColorError(value.to_string());
+        let trimmed = value.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+        let lower = trimmed.to_ascii_lowercase();
+
+        if let Some(hex) = lower.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(err);
+        }
+        if lower == "transparent" {
+            return Ok(Self::rgb8(0, 0, 0, 0.0));
+        }
+        if let Some((_, rgb)) = NAMED.iter().find(|(name, _)| *name == lower) {
+            return Ok(Self::from_u24(*rgb));
+        }
+
+        let (function, args) = lower
+            .strip_suffix(')')
+            .and_then(|s| s.split_once('('))
+            .ok_or_else(err)?;
+        let args = parse_args(args).ok_or_else(err)?;
+        match function.trim() {
+            "rgb" | "rgba" => rgb_from_args(&args),
+            "hsl" | "hsla" => hsl_from_args(&args),
+            "oklch" => oklch_from_args(&args),
+            _ => None,
+        }
+        .ok_or_else(err)
+    }
+
+    fn rgb8(r: u8, g: u8, b: u8, alpha: f64) -> Self {
+        Self {
+            r: f64::from(r) / 255.0,
+            g: f64::from(g) / 255.0,
+            b: f64::from(b) / 255.0,
+            alpha,
+        }
+    }
+
+    fn from_u24(rgb: u32) -> Self {
+        Self::rgb8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 1.0)
+    }
+
+    /// Channels clipped to the sRGB gamut and scaled to `0..=255`.
+    fn to_rgb8(self) -> [u8; 3] {
+        [self.r, self.g, self.b].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    fn clamped(self) -> Self {
+        Self {
+            r: self.r.clamp(0.0, 1.0),
+            g: self.g.clamp(0.0, 1.0),
+            b: self.b.clamp(0.0, 1.0),
+            alpha: self.alpha,
+        }
+    }
+
+    /// Writes the color in `format`, e.g. `#0f3557`, `rgb(15, 53, 87)`,
+    /// `oklch(32.14% 0.074 249.28)` or `hsl(208.3, 70.6%, 20%)`.
+    pub fn format(self, format: ColorFormat) -> String {
+        let alpha = round(self.alpha.clamp(0.0, 1.0), 3);
+        let translucent = alpha < 1.0;
+        let mut out = String::new();
+
+        match format {
+            ColorFormat::Hex => {
+                let [r, g, b] = self.to_rgb8();
+                let _ = write!(out, "#{r:02x}{g:02x}{b:02x}");
+                if translucent {
+                    let _ = write!(out, "{:02x}", (alpha * 255.0).round() as u8);
+                }
+            }
+            ColorFormat::Rgb => {
+                let [r, g, b] = self.to_rgb8();
+                if translucent {
+                    let _ = write!(out, "rgba({r}, {g}, {b}, {alpha})");
+                } else {
+                    let _ = write!(out, "rgb({r}, {g}, {b})");
+                }
+            }
+            ColorFormat::Hsl => {
+                let (h, s, l) = self.clamped().to_hsl();
+                // A hue just below 360 rounds to 360, which is 0
+                let (h, s, l) = (
+                    round(h, 1) % 360.0,
+                    round(s * 100.0, 1),
+                    round(l * 100.0, 1),
+                );
+                if translucent {
+                    let _ = write!(out, "hsla({h}, {s}%, {l}%, {alpha})");
+                } else {
+                    let _ = write!(out, "hsl({h}, {s}%, {l}%)");
+                }
+            }
+            ColorFormat::Oklch => {
+                let (l, c, h) = self.to_oklch();
+                let _ = write!(
+                    out,
+                    "oklch({}% {} {}",
+                    round(l * 100.0, 2),
+                    round(c, 4),
+                    round(h, 2) % 360.0
+                );
+                if translucent {
+                    let _ = write!(out, " / {alpha}");
+                }
+                out.push(')');
+            }
+        }
+
+        out
+    }
+
+    /// WCAG 2.x relative luminance of the color clipped to sRGB, ignoring alpha.
+    pub fn relative_luminance(self) -> f64 {
+        let c = self.clamped();
+        0.2126 * to_linear(c.r) + 0.7152 * to_linear(c.g) + 0.0722 * to_linear(c.b)
+    }
+
+    /// WCAG 2.x contrast ratio of this color as text on `background`, from 1 to 21.
+    ///
+    /// The background is treated as opaque; a translucent foreground is blended onto it first.
+    pub fn contrast_ratio(self, background: Color) -> f64 {
+        let bg = Self {
+            alpha: 1.0,
+            ..background.clamped()
+        };
+        let fg = self.clamped();
+        let a = fg.alpha.clamp(0.0, 1.0);
+        let fg = Self {
+            r: fg.r * a + bg.r * (1.0 - a),
+            g: fg.g * a + bg.g * (1.0 - a),
+            b: fg.b * a + bg.b * (1.0 - a),
+            alpha: 1.0,
+        };
+
+        let (l1, l2) = (fg.relative_luminance(), bg.relative_luminance());
+        (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+    }
+
+    /// Hue in degrees, saturation and lightness in `0..=1`.
+    fn to_hsl(self) -> (f64, f64, f64) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let l = (max + min) / 2.0;
+        let d = max - min;
+        if d < 1e-9 {
+            return (0.0, 0.0, l);
+        }
+
+        let s = d / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == self.r {
+            60.0 * ((self.g - self.b) / d).rem_euclid(6.0)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / d + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / d + 4.0)
+        };
+        (h, s, l)
+    }
+
+    fn from_hsl(h: f64, s: f64, l: f64, alpha: f64) -> Self {
+        let h = h.rem_euclid(360.0);
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+        let m = l - c / 2.0;
+        let (r, g, b) = match (h / 60.0) as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        Self {
+            r: r + m,
+            g: g + m,
+            b: b + m,
+            alpha,
+        }
+    }
+
+    /// Lightness in `0..=1`, chroma, and hue in degrees.
+    fn to_oklch(self) -> (f64, f64, f64) {
+        let (r, g, b) = (to_linear(self.r), to_linear(self.g), to_linear(self.b));
+
+        let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+        let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+        let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+
+        let lightness = 0.210_454_255_3 * l + 0.793_617_785_0 * m - 0.004_072_046_8 * s;
+        let a = 1.977_998_495_1 * l - 2.428_592_205_0 * m + 0.450_593_709_9 * s;
+        let b = 0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766_0 * s;
+
+        let chroma = a.hypot(b);
+        // Achromatic colors have no meaningful hue
+        let hue = if chroma < 1e-4 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        };
+        (lightness, chroma, hue)
+    }
+
+    fn from_oklch(lightness: f64, chroma: f64, hue: f64, alpha: f64) -> Self {
+        let (a, b) = (
+            chroma * hue.to_radians().cos(),
+            chroma * hue.to_radians().sin(),
+        );
+
+        let l = (lightness + 0.396_337_777_4 * a + 0.215_803_757_3 * b).powi(3);
+        let m = (lightness - 0.105_561_345_8 * a - 0.063_854_172_8 * b).powi(3);
+        let s = (lightness - 0.089_484_177_5 * a - 1.291_485_548_0 * b).powi(3);
+
+        let r = 4.076_741_662_1 * l - 3.307_711_591_3 * m + 0.230_969_929_2 * s;
+        let g = -1.268_438_004_6 * l + 2.609_757_401_1 * m - 0.341_319_396_5 * s;
+        let b = -0.004_196_086_3 * l - 0.703_418_614_7 * m + 1.707_614_701_0 * s;
+
+        Self {
+            r: from_linear(r),
+            g: from_linear(g),
+            b: from_linear(b),
+            alpha,
+        }
+    }
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    let rounded = (value * factor).round() / factor;
+    // Avoid printing `-0`
+    if rounded == 0.0 { 0.0 } else { rounded }
+}
+
+fn to_linear(c: f64) -> f64 {
+    let sign = c.signum();
+    let c = c.abs();
+    sign * if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f64) -> f64 {
+    let sign = c.signum();
+    let c = c.abs();
+    sign * if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok())
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    let alpha = digits.get(3).map_or(1.0, |a| f64::from(*a) / 255.0);
+    Some(Color::rgb8(digits[0], digits[1], digits[2], alpha))
+}
+
+/// One numeric argument of a color function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arg {
+    Number(f64),
+    Percent(f64),
+    /// An angle, converted to degrees.
+    Degrees(f64),
+    /// `none`, treated as zero.
+    None,
+}
+
+/// Splits color function arguments in either `a, b, c[, alpha]` or `a b c[ / alpha]` syntax.
+/// Returns the three channels followed by the alpha, if any.
+fn parse_args(args: &str) -> Option<Vec<Arg>> {
+    let (channels, alpha) = match args.split_once('/') {
+        Some((channels, alpha)) => (channels, Some(alpha)),
+        None => (args, None),
+    };
+    let legacy = channels.contains(',');
+    let mut parts: Vec<&str> = if legacy {
+        channels.split(',').map(str::trim).collect()
+    } else {
+        channels.split_whitespace().collect()
+    };
+    // Only the comma syntax takes alpha as a fourth channel
+    let valid = match (legacy, alpha) {
+        (true, None) => (3..=4).contains(&parts.len()),
+        (false, _) => parts.len() == 3,
+        (true, Some(_)) => false,
+    };
+    if !valid {
+        return None;
+    }
+    parts.extend(alpha.map(str::trim));
+    parts.into_iter().map(parse_arg).collect()
+}
+
+fn parse_arg(arg: &str) -> Option<Arg> {
+    let number = |s: &str| s.parse::<f64>().ok().filter(|n| n.is_finite());
+    if arg == "none" {
+        Some(Arg::None)
+    } else if let Some(n) = arg.strip_suffix('%') {
+        number(n).map(Arg::Percent)
+    } else if let Some(n) = arg.strip_suffix("deg") {
+        number(n).map(Arg::Degrees)
+    } else if let Some(n) = arg.strip_suffix("grad") {
+        number(n).map(|n| Arg::Degrees(n * 0.9))
+    } else if let Some(n) = arg.strip_suffix("rad") {
+        number(n).map(|n| Arg::Degrees(n.to_degrees()))
+    } else if let Some(n) = arg.strip_suffix("turn") {
+        number(n).map(|n| Arg::Degrees(n * 360.0))
+    } else {
+        number(arg).map(Arg::Number)
+    }
+}
+
+/// Alpha as a number or percentage; `1` when absent.
+fn alpha_arg(args: &[Arg]) -> Option<f64> {
+    match args.get(3) {
+        None => Some(1.0),
+        Some(Arg::Number(a)) => Some(a.clamp(0.0, 1.0)),
+        Some(Arg::Percent(a)) => Some((a / 100.0).clamp(0.0, 1.0)),
+        Some(Arg::None) => Some(0.0),
+        Some(Arg::Degrees(_)) => None,
+    }
+}
+
+fn rgb_from_args(args: &[Arg]) -> Option<Color> {
+    let channel = |arg: &Arg| match arg {
+        Arg::Number(n) => Some(n / 255.0),
+        Arg::Percent(p) => Some(p / 100.0),
+        Arg::None => Some(0.0),
+        Arg::Degrees(_) => None,
+    };
+    Some(Color {
+        r: channel(&args[0])?.clamp(0.0, 1.0),
+        g: channel(&args[1])?.clamp(0.0, 1.0),
+        b: channel(&args[2])?.clamp(0.0, 1.0),
+        alpha: alpha_arg(args)?,
+    })
+}
+
+fn hsl_from_args(args: &[Arg]) -> Option<Color> {
+    let hue = match args[0] {
+        Arg::Number(h) | Arg::Degrees(h) => h,
+        Arg::None => 0.0,
+        Arg::Percent(_) => return None,
+    };
+    let fraction = |arg: &Arg| match arg {
+        Arg::Percent(p) => Some((p / 100.0).clamp(0.0, 1.0)),
+        Arg::Number(n) => Some((n / 100.0).clamp(0.0, 1.0)),
+        Arg::None => Some(0.0),
+        Arg::Degrees(_) => None,
+    };
+    Some(Color::from_hsl(
+        hue,
+        fraction(&args[1])?,
+        fraction(&args[2])?,
+        alpha_arg(args)?,
+    ))
+}
+
+fn oklch_from_args(args: &[Arg]) -> Option<Color> {
+    let lightness = match args[0] {
+        Arg::Percent(p) => p / 100.0,
+        Arg::Number(n) => n,
+        Arg::None => 0.0,
+        Arg::Degrees(_) => return None,
+    }
+    .clamp(0.0, 1.0);
+    // 100% chroma is 0.4 in CSS Color 4
+    let chroma = match args[1] {
+        Arg::Percent(p) => p / 100.0 * 0.4,
+        Arg::Number(n) => n,
+        Arg::None => 0.0,
+        Arg::Degrees(_) => return None,
+    }
+    .max(0.0);
+    let hue = match args[2] {
+        Arg::Number(h) | Arg::Degrees(h) => h,
+        Arg::None => 0.0,
+        Arg::Percent(_) => return None,
+    };
+    Some(Color::from_oklch(lightness, chroma, hue, alpha_arg(args)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_formats() {
+        let cases = [
+            (
+                "#0f3557",
+                "#0f3557",
+                "rgb(15, 53, 87)",
+                "hsl(208.3, 70.6%, 20%)",
+            ),
+            (
+                "\"#FFF\"",
+                "#ffffff",
+                "rgb(255, 255, 255)",
+                "hsl(0, 0%, 100%)",
+            ),
+            (
+                "#ff000080",
+                "#ff000080",
+                "rgba(255, 0, 0, 0.502)",
+                "hsla(0, 100%, 50%, 0.502)",
+            ),
+            (
+                "rgb(0 128 0 / 50%)",
+                "#00800080",
+                "rgba(0, 128, 0, 0.5)",
+                "hsla(120, 100%, 25.1%, 0.5)",
+            ),
+            (
+                "rgba(0, 0, 255, 1)",
+                "#0000ff",
+                "rgb(0, 0, 255)",
+                "hsl(240, 100%, 50%)",
+            ),
+            (
+                "hsl(120deg 100% 25%)",
+                "#008000",
+                "rgb(0, 128, 0)",
+                "hsl(120, 100%, 25%)",
+            ),
+            (
+                "oklch(62.8% 0.2577 29.23)",
+                "#ff0000",
+                "rgb(255, 0, 0)",
+                "hsl(0, 100%, 50%)",
+            ),
+            ("navy", "#000080", "rgb(0, 0, 128)", "hsl(240, 100%, 25.1%)"),
+        ];
+
+        for (input, hex, rgb, hsl) in cases {
+            let color = Color::parse(input).unwrap();
+            assert_eq!(color.format(ColorFormat::Hex), hex, "{input}");
+            assert_eq!(color.format(ColorFormat::Rgb), rgb, "{input}");
+            assert_eq!(color.format(ColorFormat::Hsl), hsl, "{input}");
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_oklch() {
+        let cases = [
+            ("#0f3557", "oklch(32.14% 0.074 249.28)"),
+            ("#ffffff", "oklch(100% 0 0)"),
+            ("#000000", "oklch(0% 0 0)"),
+            ("#ff0000", "oklch(62.8% 0.2577 29.23)"),
+            ("oklch(98% 0.002 247.839)", "oklch(98% 0.002 247.84)"),
+            ("oklch(70% 0.2 200 / 0.5)", "oklch(70% 0.2 200 / 0.5)"),
+        ];
+
+        for (input, expected) in cases {
+            let color = Color::parse(input).unwrap();
+            assert_eq!(color.format(ColorFormat::Oklch), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_unrecognized_values() {
+        for input in [
+            "",
+            "#12345",
+            "#ggg",
+            "var(--x)",
+            "rgb(1, 2)",
+            "rgb(1 2 3 4)",
+            "rgb(1, 2, 3 / 1)",
+            "oklch(50% 0.1 10%)",
+            "currentColor",
+            "chartreuse",
+        ] {
+            assert_eq!(
+                Color::parse(input),
+                Err(ParseColorError(input.to_string())),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn measures_contrast() {
+        let ratio = |fg: &str, bg: &str| {
+            let ratio = Color::parse(fg)
+                .unwrap()
+                .contrast_ratio(Color::parse(bg).unwrap());
+            round(ratio, 2)
+        };
+
+        assert_eq!(ratio("black", "white"), 21.0);
+        assert_eq!(ratio("white", "black"), 21.0);
+        assert_eq!(ratio("#fff", "white"), 1.0);
+        assert_eq!(ratio("#767676", "white"), 4.54);
+        // Half-transparent black is blended onto the background first
+        assert_eq!(ratio("rgba(0, 0, 0, 0.5)", "white"), 3.98);
+    }
+}
@@ -1,11 +1,20 @@
 use anyhow::Result;
 use serde::Deserialize;
-use std::path::Path;
+use std::{path::Path, time::Duration};
+
+use crate::{
+    country_source::CountrySource, retry::RetryPolicy, theme_audit::ContrastMode,
+    themes::DEFAULT_REQUIRED_COLORS,
+};
 
 /// Application configuration loaded from proxy.ron
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
+    /// Shorthand for `country_source: Http([url])`; ignored when `country_source` is set.
+    #[serde(default)]
     pub country_api_url: String,
+    #[serde(default)]
+    pub country_source: Option<CountrySource>,
     #[serde(default = "default_proxy_port")]
     pub proxy_port: u16,
     #[serde(default = "default_upstream_host")]
@@ -20,6 +29,44 @@ pub struct Config {
     pub rate_limit_burst_size: u32,
     #[serde(default = "default_themes_css_path")]
     pub themes_css_path: String,
+    #[serde(default = "default_themes_hot_reload")]
+    pub themes_hot_reload: bool,
+    #[serde(default = "default_themes_required_colors")]
+    pub themes_required_colors: Vec<String>,
+    #[serde(default = "default_themes_contrast_mode")]
+    pub themes_contrast_mode: ContrastMode,
+    #[serde(default = "default_country_cache_ttl_secs")]
+    pub country_cache_ttl_secs: u64,
+    #[serde(default)]
+    pub country_snapshot_path: Option<String>,
+    #[serde(default = "default_api_cache_control")]
+    pub api_cache_control: String,
+    #[serde(default = "default_api_stale_while_revalidate_secs")]
+    pub api_stale_while_revalidate_secs: u64,
+    #[serde(default = "default_country_connect_timeout_ms")]
+    pub country_connect_timeout_ms: u64,
+    #[serde(default = "default_country_request_timeout_ms")]
+    pub country_request_timeout_ms: u64,
+    #[serde(default = "default_country_max_retries")]
+    pub country_max_retries: u32,
+    #[serde(default = "default_country_retry_base_delay_ms")]
+    pub country_retry_base_delay_ms: u64,
+    #[serde(default = "default_country_retry_max_delay_ms")]
+    pub country_retry_max_delay_ms: u64,
+    #[serde(default = "default_country_breaker_failure_threshold")]
+    pub country_breaker_failure_threshold: u32,
+    #[serde(default = "default_country_breaker_cooldown_secs")]
+    pub country_breaker_cooldown_secs: u64,
+    #[serde(default = "default_country_refresh_interval_secs")]
+    pub country_refresh_interval_secs: u64,
+    #[serde(default = "default_country_change_log_size")]
+    pub country_change_log_size: usize,
+    #[serde(default = "default_flag_cache_dir")]
+    pub flag_cache_dir: String,
+    #[serde(default = "default_flag_png_url")]
+    pub flag_png_url: String,
+    #[serde(default = "default_flag_svg_url")]
+    pub flag_svg_url: String,
 }
 
 fn default_proxy_port() -> u16 {
@@ -50,6 +97,81 @@ fn default_themes_css_path() -> String {
     "src/styles.css".to_string()
 }
 
+fn default_themes_hot_reload() -> bool {
+    true
+}
+
+fn default_themes_required_colors() -> Vec<String> {
+    DEFAULT_REQUIRED_COLORS
+        .iter()
+        .map(|c| c.to_string())
+        .collect()
+}
+
+fn default_themes_contrast_mode() -> ContrastMode {
+    ContrastMode::Warn
+}
+
+fn default_country_cache_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_api_cache_control() -> String {
+    "public, max-age=60".to_string()
+}
+
+fn default_api_stale_while_revalidate_secs() -> u64 {
+    86400
+}
+
+fn default_country_connect_timeout_ms() -> u64 {
+    3000
+}
+
+fn default_country_request_timeout_ms() -> u64 {
+    10000
+}
+
+fn default_country_max_retries() -> u32 {
+    2
+}
+
+fn default_country_retry_base_delay_ms() -> u64 {
+    250
+}
+
+fn default_country_retry_max_delay_ms() -> u64 {
+    2000
+}
+
+fn default_country_breaker_failure_threshold() -> u32 {
+    3
+}
+
+fn default_country_breaker_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_country_refresh_interval_secs() -> u64 {
+    1800
+}
+
+fn default_country_change_log_size() -> usize {
+    20
+}
+
+fn default_flag_cache_dir() -> String {
+    "proxy/data/flags".to_string()
+}
+
+fn default_flag_png_url() -> String {
+    "https://flagcdn.com/w320/{code}.png".to_string()
+}
+
+fn default_flag_svg_url() -> String {
+    "https://flagcdn.com/{code}.svg".to_string()
+}
+
 impl Config {
     /// Load configuration from RON file with validation
     pub fn load(path: &Path) -> Result<Self> {
@@ -64,10 +186,27 @@ impl Config {
             upstream_host = %config.upstream_host,
             upstream_port = config.upstream_port,
             asset_dir = %config.asset_dir,
-            country_api_url = %config.country_api_url,
+            country_source = %config.country_source().describe(),
             themes_css_path = %config.themes_css_path,
+            themes_hot_reload = config.themes_hot_reload,
+            themes_required_colors = ?config.themes_required_colors,
+            themes_contrast_mode = ?config.themes_contrast_mode,
             rate_limit_per_second = config.rate_limit_per_second,
             rate_limit_burst_size = config.rate_limit_burst_size,
+            country_cache_ttl_secs = config.country_cache_ttl_secs,
+            country_snapshot_path = ?config.country_snapshot_path,
+            api_cache_control = %config.api_cache_control,
+            api_stale_while_revalidate_secs = config.api_stale_while_revalidate_secs,
+            country_connect_timeout_ms = config.country_connect_timeout_ms,
+            country_request_timeout_ms = config.country_request_timeout_ms,
+            country_max_retries = config.country_max_retries,
+            country_breaker_failure_threshold = config.country_breaker_failure_threshold,
+            country_breaker_cooldown_secs = config.country_breaker_cooldown_secs,
+            country_refresh_interval_secs = config.country_refresh_interval_secs,
+            country_change_log_size = config.country_change_log_size,
+            flag_cache_dir = %config.flag_cache_dir,
+            flag_png_url = %config.flag_png_url,
+            flag_svg_url = %config.flag_svg_url,
             "Configuration loaded and validated"
         );
 
@@ -106,14 +245,84 @@ impl Config {
             anyhow::bail!("upstream_host cannot be empty");
         }
 
-        // Validate country API URL is not empty
-        if self.country_api_url.is_empty() {
-            anyhow::bail!("country_api_url cannot be empty");
+        // Validate the country data source
+        match self.country_source() {
+            CountrySource::Http(urls) if urls.is_empty() || urls.iter().any(String::is_empty) => {
+                anyhow::bail!("country_api_url (or every Http country_source URL) cannot be empty");
+            }
+            CountrySource::File(path) if !Path::new(&path).is_file() => {
+                anyhow::bail!("Country source file does not exist: {path}");
+            }
+            _ => {}
+        }
+
+        // Validate country cache TTL
+        if self.country_cache_ttl_secs == 0 {
+            anyhow::bail!("country_cache_ttl_secs cannot be 0");
+        }
+
+        // Validate snapshot path is not empty when provided
+        if self.country_snapshot_path.as_deref() == Some("") {
+            anyhow::bail!("country_snapshot_path cannot be empty");
+        }
+
+        // Validate API Cache-Control is not empty
+        if self.api_cache_control.trim().is_empty() {
+            anyhow::bail!("api_cache_control cannot be empty");
+        }
+
+        // Validate upstream timeouts
+        if self.country_connect_timeout_ms == 0 || self.country_request_timeout_ms == 0 {
+            anyhow::bail!("country_connect_timeout_ms and country_request_timeout_ms cannot be 0");
+        }
+
+        if self.country_retry_base_delay_ms > self.country_retry_max_delay_ms {
+            anyhow::bail!("country_retry_base_delay_ms cannot exceed country_retry_max_delay_ms");
+        }
+
+        if self.country_breaker_failure_threshold == 0 {
+            anyhow::bail!("country_breaker_failure_threshold cannot be 0");
+        }
+
+        // Validate required theme colors are bare names like `base-100`
+        if let Some(color) = self
+            .themes_required_colors
+            .iter()
+            .find(|c| c.trim().is_empty() || c.starts_with("--"))
+        {
+            anyhow::bail!(
+                "themes_required_colors entries must be color names without `--color-`, got {color:?}"
+            );
+        }
+
+        // Validate the flag cache directory and upstream URL templates
+        if self.flag_cache_dir.trim().is_empty() {
+            anyhow::bail!("flag_cache_dir cannot be empty");
+        }
+
+        if !self.flag_png_url.contains("{code}") || !self.flag_svg_url.contains("{code}") {
+            anyhow::bail!("flag_png_url and flag_svg_url must contain a {{code}} placeholder");
         }
 
         Ok(())
     }
 
+    /// Resolved country data source (defaults to HTTP from `country_api_url`)
+    pub fn country_source(&self) -> CountrySource {
+        self.country_source
+            .clone()
+            .unwrap_or_else(|| CountrySource::Http(vec![self.country_api_url.clone()]))
+    }
+
+    /// Backoff policy for retrying the country fetch
+    pub fn country_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.country_max_retries,
+            base_delay: Duration::from_millis(self.country_retry_base_delay_ms),
+            max_delay: Duration::from_millis(self.country_retry_max_delay_ms),
+        }
+    }
+
     /// Get the full upstream base URL
     pub fn upstream_base(&self) -> String {
         format!("http://{}:{}", self.upstream_host, self.upstream_port)
@@ -0,0 +1,518 @@
+//! Typed country models: the upstream restcountries.com shape and the simplified
+//! public shape served by `/api/country`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::{
+    flag_cache::{FlagFormat, proxied_url},
+    locale::NameLocale,
+};
+
+/// Version of the public [`Country`] shape.
+///
+/// Every rendered record carries it as [`SCHEMA_VERSION_FIELD`], so it survives saving or
+/// exporting a response, and country endpoints also send it in the `X-Schema-Version` header.
+/// Bump only when the wire shape changes (a field added, removed or changing meaning) and say
+/// why in the commit.
+pub const COUNTRY_SCHEMA_VERSION: u32 = 9;
+
+/// Field added to every rendered record (and CSV row) whatever `?fields=` selects.
+pub const SCHEMA_VERSION_FIELD: &str = "schemaVersion";
+
+/// A single record as returned by restcountries.com v3.1.
+///
+/// Every field we rely on is required, so a schema change upstream surfaces as a
+/// deserialization error instead of silently producing empty values.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpstreamCountry {
+    pub name: UpstreamName,
+    pub cca2: String,
+    /// Optional because older field lists did not request it.
+    #[serde(default)]
+    pub cca3: Option<String>,
+    pub region: String,
+    /// Empty or missing for a handful of territories.
+    #[serde(default)]
+    pub subregion: Option<String>,
+    pub population: u64,
+    pub flags: UpstreamFlags,
+    #[serde(default, rename = "altSpellings")]
+    pub alt_spellings: Vec<String>,
+    #[serde(default)]
+    pub capital: Vec<String>,
+    /// ISO 639-3 code to English language name.
+    #[serde(default)]
+    pub languages: BTreeMap<String, String>,
+    /// ISO 4217 code to currency details.
+    #[serde(default)]
+    pub currencies: BTreeMap<String, UpstreamCurrency>,
+    /// cca3 codes of land neighbours.
+    #[serde(default)]
+    pub borders: Vec<String>,
+    /// ISO 639-3 code to translated names.
+    #[serde(default)]
+    pub translations: BTreeMap<String, UpstreamTranslation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpstreamName {
+    pub common: String,
+    #[serde(default)]
+    pub official: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpstreamFlags {
+    pub png: String,
+    #[serde(default)]
+    pub svg: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpstreamTranslation {
+    pub common: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpstreamCurrency {
+    pub name: String,
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+
+/// Simplified country record.
+///
+/// Serializing it writes every field, which is what the snapshot stores; responses go through
+/// a [`FieldSelection`] so the detail fields are only sent when asked for.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Country {
+    /// Upper-case ISO 3166-1 alpha-2 code.
+    pub code: String,
+    /// Upper-case ISO 3166-1 alpha-3 code, when upstream provided one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cca3: Option<String>,
+    pub name: String,
+    /// Official name, when upstream provided one that differs from `name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub official_name: Option<String>,
+    /// Alternate spellings and endonyms, used by search.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alt_spellings: Vec<String>,
+    pub region: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subregion: Option<String>,
+    pub population: u64,
+    /// PNG flag URL, served by the proxy's `/api/flags` cache.
+    pub flag: String,
+    /// SVG flag URL on the same cache, when upstream provided one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag_svg: Option<String>,
+    /// Regional-indicator emoji derived from `code`.
+    #[serde(default)]
+    pub flag_emoji: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capital: Vec<String>,
+    /// Language names, ordered by ISO 639-3 code.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    /// Currencies, ordered by ISO 4217 code.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub currencies: Vec<Currency>,
+    /// Upper-case cca3 codes of land neighbours.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub borders: Vec<String>,
+    /// Common name by ISO 639-3 language code, used for `lang=` / `Accept-Language`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Currency {
+    /// ISO 4217 code.
+    pub code: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+/// Field names accepted by `?fields=`, in serialization order.
+pub const COUNTRY_FIELDS: &[&str] = &[
+    "code",
+    "cca3",
+    "name",
+    "officialName",
+    "altSpellings",
+    "region",
+    "subregion",
+    "population",
+    "flag",
+    "flagSvg",
+    "flagEmoji",
+    "capital",
+    "languages",
+    "currencies",
+    "borders",
+    "translations",
+];
+
+/// Fields sent when `?fields=` is absent: the simplified shape the frontend `Country` type
+/// expects. The rest of [`COUNTRY_FIELDS`] must be requested by name.
+pub const DEFAULT_COUNTRY_FIELDS: &[&str] = &[
+    "code",
+    "cca3",
+    "name",
+    "region",
+    "subregion",
+    "population",
+    "flag",
+    "flagSvg",
+    "flagEmoji",
+];
+
+/// Requested field names that are not in [`COUNTRY_FIELDS`].
+#[derive(Debug, thiserror::Error)]
+#[error("unknown fields: {}", .0.join(", "))]
+pub struct UnknownFields(pub Vec<String>);
+
+/// A validated `?fields=` selection, in the order requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSelection(Vec<&'static str>);
+
+impl FieldSelection {
+    /// Parses a comma-separated list, ignoring blanks and duplicates.
+    ///
+    /// Returns `Ok(None)` when no field is named, meaning the [default](Self::default) fields.
+    pub fn parse(raw: &str) -> Result<Option<Self>, UnknownFields> {
+        let mut fields = Vec::new();
+        let mut unknown = Vec::new();
+
+        for name in raw.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match COUNTRY_FIELDS.iter().find(|f| **f == name) {
+                Some(field) if !fields.contains(field) => fields.push(*field),
+                Some(_) => {}
+                None => unknown.push(name.to_string()),
+            }
+        }
+
+        if !unknown.is_empty() {
+            return Err(UnknownFields(unknown));
+        }
+        Ok((!fields.is_empty()).then_some(Self(fields)))
+    }
+
+    /// Serializes `countries` as a JSON array of the selected fields.
+    pub fn project_all(&self, countries: &[Country]) -> Value {
+        Value::Array(countries.iter().map(|c| self.project(c)).collect())
+    }
+
+    pub fn fields(&self) -> &[&'static str] {
+        &self.0
+    }
+
+    /// Serializes only the selected fields of `country`, plus [`SCHEMA_VERSION_FIELD`]; unset
+    /// optional fields stay omitted.
+    pub fn project(&self, country: &Country) -> Value {
+        let Ok(Value::Object(mut all)) = serde_json::to_value(country) else {
+            unreachable!("Country serializes to an object");
+        };
+
+        let mut selected: Map<String, Value> = self
+            .0
+            .iter()
+            .filter_map(|field| Some((field.to_string(), all.remove(*field)?)))
+            .collect();
+        selected.insert(SCHEMA_VERSION_FIELD.into(), COUNTRY_SCHEMA_VERSION.into());
+        Value::Object(selected)
+    }
+}
+
+impl Default for FieldSelection {
+    /// [`DEFAULT_COUNTRY_FIELDS`].
+    fn default() -> Self {
+        Self(DEFAULT_COUNTRY_FIELDS.to_vec())
+    }
+}
+
+/// Reasons an upstream record cannot be turned into a [`Country`].
+#[derive(Debug, thiserror::Error)]
+pub enum CountryError {
+    #[error("record does not match the restcountries schema: {0}")]
+    Schema(#[from] serde_json::Error),
+    #[error("invalid cca2 code {0:?}")]
+    InvalidCode(String),
+    #[error("country {0} has an empty common name")]
+    EmptyName(String),
+}
+
+impl TryFrom<UpstreamCountry> for Country {
+    type Error = CountryError;
+
+    fn try_from(upstream: UpstreamCountry) -> Result<Self, Self::Error> {
+        let code = upstream.cca2.trim().to_ascii_uppercase();
+        if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(CountryError::InvalidCode(upstream.cca2));
+        }
+
+        let name = upstream.name.common.trim().to_string();
+        if name.is_empty() {
+            return Err(CountryError::EmptyName(code));
+        }
+
+        // Flags are served through `/api/flags` rather than linking the CDN directly
+        let flag = if upstream.flags.png.is_empty() {
+            String::new()
+        } else {
+            proxied_url(&code, FlagFormat::Png)
+        };
+        let flag_svg = upstream
+            .flags
+            .svg
+            .filter(|s| !s.is_empty())
+            .map(|_| proxied_url(&code, FlagFormat::Svg));
+
+        Ok(Country {
+            flag_emoji: flag_emoji(&code),
+            flag,
+            flag_svg,
+            code,
+            cca3: upstream
+                .cca3
+                .map(|c| c.trim().to_ascii_uppercase())
+                .filter(|c| !c.is_empty()),
+            official_name: upstream
+                .name
+                .official
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty() && *s != name),
+            alt_spellings: upstream
+                .alt_spellings
+                .into_iter()
+                .map(|s| s.trim().to_string())
+                // Two-letter entries just repeat the code
+                .filter(|s| s.chars().count() > 2 && *s != name)
+                .collect(),
+            name,
+            region: upstream.region,
+            subregion: upstream
+                .subregion
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            population: upstream.population,
+            capital: upstream.capital,
+            languages: upstream.languages.into_values().collect(),
+            currencies: upstream
+                .currencies
+                .into_iter()
+                .map(|(code, currency)| Currency {
+                    code,
+                    name: currency.name,
+                    symbol: currency.symbol.filter(|s| !s.is_empty()),
+                })
+                .collect(),
+            borders: upstream
+                .borders
+                .into_iter()
+                .map(|b| b.trim().to_ascii_uppercase())
+                .collect(),
+            translations: upstream
+                .translations
+                .into_iter()
+                .map(|(lang, t)| (lang, t.common.trim().to_string()))
+                .filter(|(_, name)| !name.is_empty())
+                .collect(),
+        })
+    }
+}
+
+impl Country {
+    /// Converts one raw upstream JSON record.
+    pub fn from_upstream_value(value: Value) -> Result<Self, CountryError> {
+        let upstream: UpstreamCountry = serde_json::from_value(value)?;
+        Country::try_from(upstream)
+    }
+}
+
+/// Builds the flag emoji for a two-letter code from its regional-indicator symbols.
+///
+/// Returns an empty string if `code` is not two ASCII letters.
+pub fn flag_emoji(code: &str) -> String {
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return String::new();
+    }
+
+    code.chars()
+        .filter_map(|c| char::from_u32(0x1F1E6 + (c.to_ascii_uppercase() as u32 - 'A' as u32)))
+        .collect()
+}
+
+/// Converts a raw restcountries payload into simplified countries sorted by name
+/// (English collation).
+///
+/// Invalid records are logged and skipped; if none of the records convert, the
+/// upstream schema has most likely changed and an error is returned instead.
+pub fn simplify(items: Vec<Value>) -> anyhow::Result<Vec<Country>> {
+    let total = items.len();
+    let mut countries: Vec<Country> = Vec::with_capacity(total);
+
+    for (idx, item) in items.into_iter().enumerate() {
+        match Country::from_upstream_value(item) {
+            Ok(country) => countries.push(country),
+            Err(e) => tracing::warn!(idx, error = %e, "Skipping invalid upstream country"),
+        }
+    }
+
+    if countries.is_empty() && total > 0 {
+        anyhow::bail!("None of the {total} upstream country records matched the expected schema");
+    }
+
+    let english = NameLocale::english();
+    countries.sort_by(|a, b| english.compare(&a.name, &b.name));
+
+    Ok(countries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FIXTURE: &str = include_str!("../fixtures/restcountries.json");
+
+    fn fixture() -> Vec<Value> {
+        serde_json::from_str(FIXTURE).expect("fixture should be valid JSON")
+    }
+
+    #[test]
+    fn simplifies_recorded_fixture() {
+        let raw = fixture();
+        let total = raw.len();
+        let countries = simplify(raw).unwrap();
+
+        assert_eq!(countries.len(), total);
+        let names: Vec<&str> = countries.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names.first(), Some(&"Åland Islands"));
+        assert_eq!(names.last(), Some(&"United States"));
+
+        let france = countries.iter().find(|c| c.code == "FR").unwrap();
+        assert_eq!(
+            *france,
+            Country {
+                code: "FR".into(),
+                cca3: Some("FRA".into()),
+                name: "France".into(),
+                official_name: Some("French Republic".into()),
+                alt_spellings: vec!["French Republic".into(), "République française".into()],
+                region: "Europe".into(),
+                subregion: Some("Western Europe".into()),
+                population: 67391582,
+                flag: "/api/flags/fr.png".into(),
+                flag_svg: Some("/api/flags/fr.svg".into()),
+                flag_emoji: "🇫🇷".into(),
+                capital: vec!["Paris".into()],
+                languages: vec!["French".into()],
+                currencies: vec![Currency {
+                    code: "EUR".into(),
+                    name: "Euro".into(),
+                    symbol: Some("€".into()),
+                }],
+                borders: ["AND", "BEL", "DEU", "ITA", "LUX", "MCO", "ESP", "CHE"]
+                    .map(String::from)
+                    .to_vec(),
+                translations: [
+                    ("deu", "Frankreich"),
+                    ("fra", "France"),
+                    ("jpn", "フランス"),
+                    ("spa", "Francia"),
+                ]
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .into(),
+            }
+        );
+    }
+
+    #[test]
+    fn selects_allowlisted_fields() {
+        let country = Country {
+            code: "JP".into(),
+            name: "Japan".into(),
+            capital: vec!["Tokyo".into()],
+            ..Default::default()
+        };
+
+        let selection = FieldSelection::parse(" name, capital,name,cca3")
+            .unwrap()
+            .unwrap();
+        assert_eq!(selection.fields(), ["name", "capital", "cca3"]);
+        assert_eq!(
+            selection.project(&country),
+            json!({"name": "Japan", "capital": ["Tokyo"], "schemaVersion": COUNTRY_SCHEMA_VERSION})
+        );
+
+        assert!(FieldSelection::parse(" , ").unwrap().is_none());
+        // Detail fields are opt-in
+        assert_eq!(
+            FieldSelection::default().project(&country),
+            json!({
+                "code": "JP",
+                "name": "Japan",
+                "region": "",
+                "population": 0,
+                "flag": "",
+                "flagEmoji": "",
+                "schemaVersion": COUNTRY_SCHEMA_VERSION,
+            })
+        );
+        let err = FieldSelection::parse("name,capitol,secret").unwrap_err();
+        assert_eq!(err.0, ["capitol", "secret"]);
+    }
+
+    #[test]
+    fn derives_flag_emoji_from_code() {
+        assert_eq!(flag_emoji("jp"), "🇯🇵");
+        assert_eq!(flag_emoji("AX"), "🇦🇽");
+        assert_eq!(flag_emoji("USA"), "");
+        assert_eq!(flag_emoji("1A"), "");
+    }
+
+    #[test]
+    fn rejects_records_missing_required_fields() {
+        let mut record = fixture().remove(0);
+        record.as_object_mut().unwrap().remove("population");
+
+        let err = Country::from_upstream_value(record).unwrap_err();
+        assert!(matches!(err, CountryError::Schema(_)), "{err}");
+        assert!(err.to_string().contains("population"), "{err}");
+    }
+
+    #[test]
+    fn rejects_invalid_codes_and_names() {
+        let mut record = fixture().remove(0);
+        record["cca2"] = json!("FRA");
+        assert!(matches!(
+            Country::from_upstream_value(record.clone()),
+            Err(CountryError::InvalidCode(_))
+        ));
+
+        record["cca2"] = json!("fr");
+        record["name"]["common"] = json!("  ");
+        assert!(matches!(
+            Country::from_upstream_value(record),
+            Err(CountryError::EmptyName(code)) if code == "FR"
+        ));
+    }
+
+    #[test]
+    fn skips_bad_records_but_fails_on_schema_change() {
+        let mut raw = fixture();
+        raw.push(json!({"cca2": "XX"}));
+        let total = raw.len();
+        assert_eq!(simplify(raw).unwrap().len(), total - 1);
+
+        let renamed: Vec<Value> = vec![json!({"alpha2": "FR", "names": {"common": "France"}})];
+        assert!(simplify(renamed).is_err());
+    }
+}
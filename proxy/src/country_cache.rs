@@ -0,0 +1,343 @@
+use anyhow::Context;
+use axum::body::Bytes;
+use metrics::{counter, gauge};
+use serde::Serialize;
+use std::{
+    future::Future,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    country::{self, Country, FieldSelection},
+    country_graph::BorderGraph,
+    country_search::SearchIndex,
+    http_cache,
+    single_flight::{Flight, SingleFlight},
+};
+
+/// Handle to an upstream fetch that is running (or has just finished).
+pub type Inflight = Flight<CountryData>;
+
+/// Simplified country records plus their serialized JSON, kept side by side so list
+/// responses never re-serialize and lookups never re-parse.
+#[derive(Clone)]
+pub struct CountryData {
+    pub countries: Arc<Vec<Country>>,
+    /// The unfiltered `/api/country` response: every record with the default fields.
+    pub body: Bytes,
+    /// Every field of every record, as written to the snapshot.
+    pub records: Bytes,
+    /// Strong ETag of `records`, computed once per data version. It covers the detail fields
+    /// too, so it changes whenever any response derived from this data could.
+    pub etag: String,
+    /// Search index over `countries`, built once per data version.
+    pub search: Arc<SearchIndex>,
+    /// Land-border graph over `countries`, built once per data version.
+    pub graph: Arc<BorderGraph>,
+}
+
+impl CountryData {
+    /// Builds the dataset from simplified records, serializing them once.
+    pub fn from_countries(countries: Vec<Country>) -> anyhow::Result<Self> {
+        let records =
+            serde_json::to_vec(&countries).context("Failed to serialize simplified JSON")?;
+        let body = serde_json::to_vec(&FieldSelection::default().project_all(&countries))
+            .context("Failed to serialize simplified JSON")?;
+
+        Ok(Self {
+            search: Arc::new(SearchIndex::build(&countries)),
+            graph: Arc::new(BorderGraph::build(&countries)),
+            countries: Arc::new(countries),
+            etag: http_cache::strong_etag(&[&records]),
+            records: Bytes::from(records),
+            body: Bytes::from(body),
+        })
+    }
+
+    /// Builds the dataset from serialized records, such as a snapshot.
+    ///
+    /// Payloads written before `flagEmoji` existed are backfilled.
+    pub fn from_records(records: &[u8]) -> anyhow::Result<Self> {
+        let mut countries: Vec<Country> = serde_json::from_slice(records)
+            .context("Country data is not a JSON array of countries")?;
+
+        for c in countries.iter_mut().filter(|c| c.flag_emoji.is_empty()) {
+            c.flag_emoji = country::flag_emoji(&c.code);
+        }
+
+        Self::from_countries(countries)
+    }
+
+    /// Finds a country by its cca2 or cca3 code, ignoring case.
+    pub fn find(&self, code: &str) -> Option<&Country> {
+        self.position(code).map(|idx| &self.countries[idx])
+    }
+
+    /// Position in `countries` of the country with this cca2 or cca3 code, ignoring case.
+    pub fn position(&self, code: &str) -> Option<usize> {
+        self.countries.iter().position(|c| {
+            c.code.eq_ignore_ascii_case(code)
+                || c.cca3
+                    .as_deref()
+                    .is_some_and(|c| c.eq_ignore_ascii_case(code))
+        })
+    }
+}
+
+/// Where the cached country payload came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSource {
+    /// Fetched from the configured country API.
+    Upstream,
+    /// Loaded from the on-disk snapshot of the last good payload.
+    Snapshot,
+}
+
+impl DataSource {
+    /// Value used for the `X-Data-Source` response header.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DataSource::Upstream => "upstream",
+            DataSource::Snapshot => "snapshot",
+        }
+    }
+}
+
+/// A cached country dataset together with its origin.
+#[derive(Clone)]
+pub struct CachedCountries {
+    pub data: CountryData,
+    pub source: DataSource,
+}
+
+/// Result of looking up the cached country payload.
+pub enum CacheLookup {
+    /// Entry is within its TTL and can be served as-is.
+    Fresh(CachedCountries),
+    /// Entry is past its TTL (or came from a snapshot); it is still served while a refresh runs.
+    Stale(CachedCountries),
+    /// Nothing has been cached yet.
+    Missing,
+}
+
+struct CacheEntry {
+    data: CachedCountries,
+    fetched_at: Instant,
+}
+
+/// Outcome of the most recent upstream refreshes, reported on the health endpoint.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshStatus {
+    /// Unix time (seconds) of the last successful refresh.
+    pub last_success_at: Option<u64>,
+    /// Unix time (seconds) of the last failed refresh.
+    pub last_error_at: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+/// In-memory cache for the simplified country JSON with stale-while-revalidate semantics.
+///
+/// Upstream fetches are single-flight: while one is running, further refresh requests
+/// join it instead of issuing their own.
+pub struct CountryCache {
+    ttl: Duration,
+    entry: RwLock<Option<CacheEntry>>,
+    flights: SingleFlight<(), CountryData>,
+    status: RwLock<RefreshStatus>,
+}
+
+impl CountryCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: RwLock::new(None),
+            flights: SingleFlight::new(),
+            status: RwLock::new(RefreshStatus::default()),
+        }
+    }
+
+    /// Returns when the last refresh succeeded and why the last one failed.
+    pub fn refresh_status(&self) -> RefreshStatus {
+        self.status
+            .read()
+            .expect("refresh status lock poisoned")
+            .clone()
+    }
+
+    fn record_outcome(&self, outcome: &anyhow::Result<CountryData>) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let mut status = self.status.write().expect("refresh status lock poisoned");
+        match outcome {
+            Ok(_) => {
+                status.last_success_at = Some(now);
+                gauge!("country_last_refresh_success_timestamp_seconds").set(now as f64);
+            }
+            Err(e) => {
+                status.last_error_at = Some(now);
+                status.last_error = Some(format!("{e:#}"));
+                gauge!("country_last_refresh_error_timestamp_seconds").set(now as f64);
+            }
+        }
+    }
+
+    /// Returns the cached payload, classified by whether it is still within its TTL.
+    pub fn lookup(&self) -> CacheLookup {
+        let guard = self.entry.read().expect("country cache lock poisoned");
+        match guard.as_ref() {
+            Some(entry)
+                if entry.data.source == DataSource::Upstream
+                    && entry.fetched_at.elapsed() < self.ttl =>
+            {
+                CacheLookup::Fresh(entry.data.clone())
+            }
+            Some(entry) => CacheLookup::Stale(entry.data.clone()),
+            None => CacheLookup::Missing,
+        }
+    }
+
+    /// Replaces the cached payload and resets its age.
+    pub fn store(&self, data: CountryData, source: DataSource) {
+        let mut guard = self.entry.write().expect("country cache lock poisoned");
+        *guard = Some(CacheEntry {
+            data: CachedCountries { data, source },
+            fetched_at: Instant::now(),
+        });
+    }
+
+    /// Starts a refresh using `fetch`, or joins the one already in flight, counting the join
+    /// in `country_fetch_coalesced_total`.
+    ///
+    /// A successful result is stored before it is published.
+    pub fn refresh<F, Fut>(self: &Arc<Self>, fetch: F) -> Inflight
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<CountryData>> + Send + 'static,
+    {
+        let inflight = self.flights.run((), || {
+            let fut = fetch();
+            let cache = Arc::clone(self);
+            async move {
+                let outcome = fut.await;
+                if let Ok(data) = &outcome {
+                    cache.store(data.clone(), DataSource::Upstream);
+                }
+                cache.record_outcome(&outcome);
+                outcome
+            }
+        });
+
+        if inflight.coalesced {
+            counter!("country_fetch_coalesced_total").increment(1);
+            tracing::debug!("Joined in-flight country fetch");
+        }
+        inflight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn data() -> CountryData {
+        CountryData::from_countries(vec![Country {
+            code: "FR".into(),
+            name: "France".into(),
+            ..Default::default()
+        }])
+        .unwrap()
+    }
+
+    fn is_fresh(lookup: CacheLookup) -> Option<bool> {
+        match lookup {
+            CacheLookup::Fresh(_) => Some(true),
+            CacheLookup::Stale(_) => Some(false),
+            CacheLookup::Missing => None,
+        }
+    }
+
+    #[test]
+    fn upstream_data_is_fresh_within_its_ttl() {
+        let cache = CountryCache::new(Duration::from_secs(3600));
+        assert_eq!(is_fresh(cache.lookup()), None);
+
+        cache.store(data(), DataSource::Upstream);
+        assert_eq!(is_fresh(cache.lookup()), Some(true));
+    }
+
+    #[test]
+    fn expired_data_is_served_stale() {
+        let cache = CountryCache::new(Duration::from_millis(50));
+        cache.store(data(), DataSource::Upstream);
+        std::thread::sleep(Duration::from_millis(80));
+
+        let CacheLookup::Stale(cached) = cache.lookup() else {
+            panic!("expired entry should be stale");
+        };
+        assert_eq!(cached.source, DataSource::Upstream);
+        assert_eq!(cached.data.countries[0].code, "FR");
+
+        // Storing new data resets its age
+        cache.store(data(), DataSource::Upstream);
+        assert_eq!(is_fresh(cache.lookup()), Some(true));
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_share_one_fetch() {
+        const CALLERS: usize = 16;
+        let cache = Arc::new(CountryCache::new(Duration::from_secs(3600)));
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(tokio::sync::Barrier::new(CALLERS));
+
+        let refresh = |cache: &Arc<CountryCache>, fetches: &Arc<AtomicUsize>| {
+            let fetches = Arc::clone(fetches);
+            cache.refresh(move || async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(data())
+            })
+        };
+
+        let callers: Vec<_> = (0..CALLERS)
+            .map(|_| {
+                let (cache, fetches, barrier) = (
+                    Arc::clone(&cache),
+                    Arc::clone(&fetches),
+                    Arc::clone(&barrier),
+                );
+                tokio::spawn(async move {
+                    barrier.wait().await;
+                    let inflight = refresh(&cache, &fetches);
+                    let coalesced = inflight.coalesced;
+                    (coalesced, inflight.wait().await.is_ok())
+                })
+            })
+            .collect();
+
+        let mut coalesced = 0;
+        for caller in callers {
+            let (joined, ok) = caller.await.unwrap();
+            assert!(ok);
+            coalesced += usize::from(joined);
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(coalesced, CALLERS - 1);
+        assert_eq!(is_fresh(cache.lookup()), Some(true));
+
+        // Once the flight has landed, the next refresh fetches again
+        assert!(refresh(&cache, &fetches).wait().await.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn snapshot_data_is_always_stale() {
+        let cache = CountryCache::new(Duration::from_secs(3600));
+        cache.store(data(), DataSource::Snapshot);
+        assert_eq!(is_fresh(cache.lookup()), Some(false));
+    }
+}
@@ -0,0 +1,268 @@
+//! Differences between consecutive country datasets, kept for `/api/country/changes`.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::country::{COUNTRY_FIELDS, Country};
+
+/// A country that was added or removed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CountryRef {
+    pub code: String,
+    pub name: String,
+}
+
+/// One field whose value differs; a field that is absent on one side is `null` there.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// A country present in both datasets with at least one differing field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CountryChange {
+    pub code: String,
+    pub name: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// Added, removed and changed countries between two datasets, each sorted by code.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CountryDiff {
+    pub added: Vec<CountryRef>,
+    pub removed: Vec<CountryRef>,
+    pub changed: Vec<CountryChange>,
+}
+
+impl CountryDiff {
+    /// Compares `before` and `after`, matching records by `code`.
+    pub fn between(before: &[Country], after: &[Country]) -> Self {
+        let before: BTreeMap<&str, &Country> =
+            before.iter().map(|c| (c.code.as_str(), c)).collect();
+        let after: BTreeMap<&str, &Country> = after.iter().map(|c| (c.code.as_str(), c)).collect();
+        let reference = |country: &Country| CountryRef {
+            code: country.code.clone(),
+            name: country.name.clone(),
+        };
+
+        let mut diff = Self::default();
+        for (code, new) in &after {
+            match before.get(code) {
+                None => diff.added.push(reference(new)),
+                Some(old) if old != new => diff.changed.push(CountryChange {
+                    code: new.code.clone(),
+                    name: new.name.clone(),
+                    fields: changed_fields(old, new),
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .iter()
+            .filter(|(code, _)| !after.contains_key(*code))
+            .map(|(_, old)| reference(old))
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Fields that differ, in [`COUNTRY_FIELDS`] order.
+fn changed_fields(old: &Country, new: &Country) -> Vec<FieldChange> {
+    let as_object = |country: &Country| match serde_json::to_value(country) {
+        Ok(Value::Object(map)) => map,
+        _ => unreachable!("Country serializes to an object"),
+    };
+    let (mut old, mut new) = (as_object(old), as_object(new));
+
+    COUNTRY_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let before = old.remove(*field).unwrap_or(Value::Null);
+            let after = new.remove(*field).unwrap_or(Value::Null);
+            (before != after).then_some(FieldChange {
+                field,
+                before,
+                after,
+            })
+        })
+        .collect()
+}
+
+/// A recorded diff and the refresh that produced it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEntry {
+    /// Unix time (seconds) of the refresh.
+    pub detected_at: u64,
+    /// Data version (the `/api/country` ETag, unquoted) before and after the refresh.
+    pub previous_version: String,
+    pub version: String,
+    #[serde(flatten)]
+    pub diff: CountryDiff,
+}
+
+/// The most recent non-empty dataset diffs, newest first.
+pub struct ChangeLog {
+    capacity: usize,
+    entries: Mutex<VecDeque<ChangeEntry>>,
+}
+
+impl ChangeLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Logs `diff` and keeps it, dropping the oldest entry once `capacity` is reached.
+    /// Empty diffs are ignored.
+    pub fn record(&self, previous_version: &str, version: &str, diff: CountryDiff) {
+        if diff.is_empty() {
+            tracing::debug!(version, "Country data unchanged after refresh");
+            return;
+        }
+
+        for country in &diff.added {
+            tracing::info!(code = %country.code, name = %country.name, "Country added");
+        }
+        for country in &diff.removed {
+            tracing::info!(code = %country.code, name = %country.name, "Country removed");
+        }
+        for change in &diff.changed {
+            let fields: Vec<&str> = change.fields.iter().map(|f| f.field).collect();
+            tracing::info!(code = %change.code, fields = ?fields, "Country changed");
+        }
+        tracing::info!(
+            added = diff.added.len(),
+            removed = diff.removed.len(),
+            changed = diff.changed.len(),
+            previous_version,
+            version,
+            "Country data changed"
+        );
+
+        if self.capacity == 0 {
+            return;
+        }
+        let detected_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let mut entries = self.entries.lock().expect("change log lock poisoned");
+        if entries.len() == self.capacity {
+            entries.pop_back();
+        }
+        entries.push_front(ChangeEntry {
+            detected_at,
+            previous_version: previous_version.trim_matches('"').to_string(),
+            version: version.trim_matches('"').to_string(),
+            diff,
+        });
+    }
+
+    pub fn entries(&self) -> Vec<ChangeEntry> {
+        self.entries
+            .lock()
+            .expect("change log lock poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn country(code: &str, name: &str, population: u64) -> Country {
+        Country {
+            code: code.into(),
+            name: name.into(),
+            region: "Europe".into(),
+            population,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diffs_by_code() {
+        let before = vec![
+            country("DE", "Germany", 83_000_000),
+            country("FR", "France", 67_000_000),
+            country("YU", "Yugoslavia", 23_000_000),
+        ];
+        let mut france = country("FR", "France", 68_000_000);
+        france.capital = vec!["Paris".into()];
+        let after = vec![
+            country("DE", "Germany", 83_000_000),
+            france,
+            country("RS", "Serbia", 6_600_000),
+        ];
+
+        let diff = CountryDiff::between(&before, &after);
+        assert_eq!(
+            diff.added.iter().map(|c| &c.code).collect::<Vec<_>>(),
+            ["RS"]
+        );
+        assert_eq!(
+            diff.removed.iter().map(|c| &c.code).collect::<Vec<_>>(),
+            ["YU"]
+        );
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].fields,
+            [
+                FieldChange {
+                    field: "population",
+                    before: json!(67_000_000),
+                    after: json!(68_000_000),
+                },
+                FieldChange {
+                    field: "capital",
+                    before: Value::Null,
+                    after: json!(["Paris"]),
+                },
+            ]
+        );
+
+        assert!(CountryDiff::between(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn keeps_the_latest_non_empty_diffs() {
+        let log = ChangeLog::new(2);
+        let added = |code: &str| CountryDiff {
+            added: vec![CountryRef {
+                code: code.into(),
+                name: code.into(),
+            }],
+            ..Default::default()
+        };
+
+        log.record("v0", "v1", added("AA"));
+        log.record("v1", "v1", CountryDiff::default());
+        log.record("v1", "v2", added("BB"));
+        log.record("v2", "v3", added("CC"));
+
+        let versions: Vec<String> = log.entries().into_iter().map(|e| e.version).collect();
+        assert_eq!(versions, ["v3", "v2"]);
+
+        let disabled = ChangeLog::new(0);
+        disabled.record("v0", "v1", added("AA"));
+        assert!(disabled.entries().is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+//! Streamed `/api/country` representations for bulk export: JSON array, CSV and NDJSON.
+
+use axum::{
+    body::{Body as AxumBody, Bytes},
+    http::{HeaderMap, header},
+};
+use futures_util::stream;
+use serde::Deserialize;
+use std::convert::Infallible;
+
+use crate::country::{COUNTRY_SCHEMA_VERSION, Country, FieldSelection, SCHEMA_VERSION_FIELD};
+
+/// Records rendered per streamed chunk.
+const CHUNK_SIZE: usize = 64;
+
+/// Response representation for `/api/country`, chosen by `format=` or `Accept`.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A single JSON array (the default).
+    #[default]
+    Json,
+    /// RFC 4180 CSV with a header row.
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" => Some(ExportFormat::Json),
+            "text/csv" => Some(ExportFormat::Csv),
+            "application/x-ndjson" | "application/ndjson" => Some(ExportFormat::Ndjson),
+            _ => None,
+        }
+    }
+
+    /// Picks the format: an explicit `format=` wins, then the highest-q supported `Accept`
+    /// media type, then JSON.
+    pub fn negotiate(explicit: Option<ExportFormat>, headers: &HeaderMap) -> Self {
+        if let Some(format) = explicit {
+            return format;
+        }
+
+        let mut best: Option<(f32, ExportFormat)> = None;
+        for range in headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+        {
+            let mut parts = range.split(';').map(str::trim);
+            let media_type = parts.next().unwrap_or_default().to_ascii_lowercase();
+            let q = parts
+                .filter_map(|p| p.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            if let Some(format) = Self::from_media_type(&media_type)
+                && q > 0.0
+                && best.is_none_or(|(best_q, _)| q > best_q)
+            {
+                best = Some((q, format));
+            }
+        }
+
+        best.map(|(_, format)| format).unwrap_or_default()
+    }
+}
+
+/// Streams `countries` in `format`, rendering `CHUNK_SIZE` records at a time.
+///
+/// JSON records contain only the selected fields, and CSV has one column per selected field.
+/// Both end with the schema version, so exported files still say which shape they hold.
+pub fn stream_body(
+    format: ExportFormat,
+    countries: Vec<Country>,
+    fields: FieldSelection,
+) -> AxumBody {
+    let mut columns: Vec<&'static str> = fields.fields().to_vec();
+    columns.push(SCHEMA_VERSION_FIELD);
+    let (head, tail) = match format {
+        ExportFormat::Json => ("[".to_string(), "]"),
+        ExportFormat::Csv => (format!("{}\r\n", columns.join(",")), ""),
+        ExportFormat::Ndjson => (String::new(), ""),
+    };
+    let to_json = move |country: &Country| {
+        serde_json::to_string(&fields.project(country)).expect("Country always serializes")
+    };
+
+    let rows = (0..countries.len()).step_by(CHUNK_SIZE).map(move |start| {
+        let mut buf = String::new();
+        let end = (start + CHUNK_SIZE).min(countries.len());
+        for (i, country) in countries[start..end].iter().enumerate() {
+            match format {
+                ExportFormat::Json => {
+                    if start + i > 0 {
+                        buf.push(',');
+                    }
+                    buf.push_str(&to_json(country));
+                }
+                ExportFormat::Csv => buf.push_str(&csv_row(country, &columns)),
+                ExportFormat::Ndjson => {
+                    buf.push_str(&to_json(country));
+                    buf.push('\n');
+                }
+            }
+        }
+        Bytes::from(buf)
+    });
+
+    let parts = std::iter::once(Bytes::from(head))
+        .chain(rows)
+        .chain(std::iter::once(Bytes::from_static(tail.as_bytes())))
+        .filter(|b| !b.is_empty())
+        .map(Ok::<_, Infallible>);
+
+    AxumBody::from_stream(stream::iter(parts))
+}
+
+/// Renders one CSV record for `columns`, terminated by CRLF.
+fn csv_row(country: &Country, columns: &[&str]) -> String {
+    let mut row = columns
+        .iter()
+        .map(|column| csv_field(&csv_value(country, column)))
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push_str("\r\n");
+    row
+}
+
+/// Flattens a field for CSV: lists are joined with `"; "`, currencies by code.
+fn csv_value(country: &Country, field: &str) -> String {
+    let opt = |value: &Option<String>| value.clone().unwrap_or_default();
+    match field {
+        "code" => country.code.clone(),
+        "cca3" => opt(&country.cca3),
+        "name" => country.name.clone(),
+        "officialName" => opt(&country.official_name),
+        "altSpellings" => country.alt_spellings.join("; "),
+        "region" => country.region.clone(),
+        "subregion" => opt(&country.subregion),
+        "population" => country.population.to_string(),
+        "flag" => country.flag.clone(),
+        "flagSvg" => opt(&country.flag_svg),
+        "flagEmoji" => country.flag_emoji.clone(),
+        "capital" => country.capital.join("; "),
+        "languages" => country.languages.join("; "),
+        "currencies" => country
+            .currencies
+            .iter()
+            .map(|c| c.code.as_str())
+            .collect::<Vec<_>>()
+            .join("; "),
+        "borders" => country.borders.join("; "),
+        "translations" => country
+            .translations
+            .iter()
+            .map(|(lang, name)| format!("{lang}={name}"))
+            .collect::<Vec<_>>()
+            .join("; "),
+        SCHEMA_VERSION_FIELD => COUNTRY_SCHEMA_VERSION.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Quotes a field if it contains a delimiter, quote or line break, doubling inner quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::country::DEFAULT_COUNTRY_FIELDS;
+    use axum::http::HeaderValue;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn negotiates_from_param_then_accept() {
+        assert_eq!(
+            ExportFormat::negotiate(Some(ExportFormat::Ndjson), &accept("text/csv")),
+            ExportFormat::Ndjson
+        );
+        assert_eq!(
+            ExportFormat::negotiate(None, &accept("text/csv;q=0.5, application/x-ndjson")),
+            ExportFormat::Ndjson
+        );
+        assert_eq!(
+            ExportFormat::negotiate(None, &accept("text/html, text/csv")),
+            ExportFormat::Csv
+        );
+        assert_eq!(
+            ExportFormat::negotiate(None, &accept("*/*")),
+            ExportFormat::Json
+        );
+        assert_eq!(
+            ExportFormat::negotiate(None, &accept("text/csv;q=0")),
+            ExportFormat::Json
+        );
+    }
+
+    fn country(code: &str, name: &str) -> Country {
+        Country {
+            code: code.into(),
+            name: name.into(),
+            region: "Europe".into(),
+            population: 1,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_valid_json_and_ndjson_across_chunks() {
+        let countries: Vec<Country> = (0..CHUNK_SIZE + 3)
+            .map(|i| country("XX", &format!("Country {i}")))
+            .collect();
+
+        let body = stream_body(
+            ExportFormat::Json,
+            countries.clone(),
+            FieldSelection::default(),
+        );
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let parsed: Vec<Country> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(parsed, countries);
+
+        let body = stream_body(
+            ExportFormat::Ndjson,
+            countries.clone(),
+            FieldSelection::default(),
+        );
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(bytes.split(|b| *b == b'\n').count(), countries.len() + 1);
+
+        let body = stream_body(ExportFormat::Json, Vec::new(), FieldSelection::default());
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(&bytes[..], b"[]");
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        let country = Country {
+            code: "CI".into(),
+            cca3: Some("CIV".into()),
+            name: "Côte d'Ivoire, \"Ivory Coast\"".into(),
+            region: "Africa".into(),
+            population: 26378275,
+            flag: "https://flagcdn.com/w320/ci.png".into(),
+            flag_emoji: "🇨🇮".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            csv_row(&country, DEFAULT_COUNTRY_FIELDS),
+            "CI,CIV,\"Côte d'Ivoire, \"\"Ivory Coast\"\"\",Africa,,26378275,https://flagcdn.com/w320/ci.png,,🇨🇮\r\n"
+        );
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+
+        let country = Country {
+            capital: vec!["Yamoussoukro".into(), "Abidjan".into()],
+            ..country
+        };
+        assert_eq!(
+            csv_row(&country, &["code", "capital"]),
+            "CI,Yamoussoukro; Abidjan\r\n"
+        );
+    }
+}
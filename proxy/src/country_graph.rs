@@ -0,0 +1,129 @@
+//! Land-border graph over the country list, used by the neighbour and route endpoints.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::country::Country;
+
+/// Land-border adjacency between countries, built once per data version from `borders`.
+///
+/// Nodes are positions in the country list; borders naming countries absent from the list
+/// are ignored and every edge is stored in both directions.
+#[derive(Debug, Default)]
+pub struct BorderGraph {
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl BorderGraph {
+    pub fn build(countries: &[Country]) -> Self {
+        let by_cca3: HashMap<&str, usize> = countries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, c)| Some((c.cca3.as_deref()?, idx)))
+            .collect();
+
+        let mut adjacency = vec![Vec::new(); countries.len()];
+        for (idx, country) in countries.iter().enumerate() {
+            for border in &country.borders {
+                if let Some(&other) = by_cca3.get(border.as_str())
+                    && other != idx
+                {
+                    adjacency[idx].push(other);
+                    adjacency[other].push(idx);
+                }
+            }
+        }
+        for neighbors in &mut adjacency {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+
+        Self { adjacency }
+    }
+
+    /// Positions of the countries bordering `idx`, in list (name) order.
+    pub fn neighbors(&self, idx: usize) -> &[usize] {
+        self.adjacency
+            .get(idx)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Shortest land path from `from` to `to` (both inclusive) by breadth-first search.
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut previous: Vec<Option<usize>> = vec![None; self.adjacency.len()];
+        let mut visited = vec![false; self.adjacency.len()];
+        let mut queue = VecDeque::from([from]);
+        *visited.get_mut(from)? = true;
+
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut path = vec![to];
+                while let Some(prev) = previous[*path.last().unwrap()] {
+                    path.push(prev);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &next in self.neighbors(node) {
+                if !visited[next] {
+                    visited[next] = true;
+                    previous[next] = Some(node);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ES-FR-DE and ES-PT are linked by land; JP is an island that lists itself as a border.
+    fn sample() -> BorderGraph {
+        let mut countries = ["ES", "FR", "JP", "PT", "DE"]
+            .map(|code| Country {
+                code: code.into(),
+                cca3: Some(format!("{code}X")),
+                ..Default::default()
+            })
+            .to_vec();
+        // One-sided border data is enough to link both countries
+        countries[0].borders = vec!["FRX".into(), "PTX".into()];
+        countries[1].borders = vec!["DEX".into(), "ESX".into(), "ZZX".into()];
+        countries[2].borders = vec!["JPX".into()];
+
+        BorderGraph::build(&countries)
+    }
+
+    #[test]
+    fn finds_shortest_land_route() {
+        let graph = sample();
+        assert_eq!(graph.neighbors(1), [0, 4]);
+        assert_eq!(graph.neighbors(3), [0]);
+
+        assert_eq!(graph.shortest_path(4, 3), Some(vec![4, 1, 0, 3]));
+        assert_eq!(graph.shortest_path(1, 1), Some(vec![1]));
+        assert_eq!(graph.shortest_path(1, 2), None);
+    }
+
+    #[test]
+    fn handles_self_borders_and_out_of_range_positions() {
+        let graph = sample();
+        // A border naming the country itself adds no edge
+        assert!(graph.neighbors(2).is_empty());
+        assert!(graph.neighbors(9).is_empty());
+
+        let cases: [(usize, usize, Option<&[usize]>); 4] =
+            [(2, 2, Some(&[2])), (0, 9, None), (9, 0, None), (9, 9, None)];
+        for (from, to, expected) in cases {
+            assert_eq!(
+                graph.shortest_path(from, to).as_deref(),
+                expected,
+                "{from} -> {to}"
+            );
+        }
+    }
+}
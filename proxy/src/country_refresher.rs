@@ -0,0 +1,101 @@
+use std::time::Duration;
+use tokio::{sync::watch, time::MissedTickBehavior};
+
+use crate::{
+    country,
+    country_cache::{CacheLookup, CountryData, Inflight},
+    country_changes::CountryDiff,
+    country_snapshot,
+    state::AppState,
+};
+
+/// Pre-warms the country cache at startup and then refreshes it every
+/// `country_refresh_interval_secs` until `shutdown` flips to `true`.
+///
+/// An interval of `0` only performs the startup load.
+pub async fn run(state: AppState, mut shutdown: watch::Receiver<bool>) {
+    let interval_secs = state.config.country_refresh_interval_secs;
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            // The first tick completes immediately, which gives us the startup pre-warm
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => break,
+        }
+
+        let inflight = start_refresh(&state);
+        tokio::select! {
+            outcome = inflight.wait() => match outcome {
+                Ok(data) => tracing::info!(countries = data.countries.len(), "Country data refreshed"),
+                Err(e) => tracing::warn!(error = %e, "Scheduled country refresh failed"),
+            },
+            _ = shutdown.changed() => break,
+        }
+
+        if interval_secs == 0 {
+            tracing::info!("Periodic country refresh disabled; refresher exiting");
+            return;
+        }
+    }
+
+    tracing::info!("Country refresher stopped");
+}
+
+/// Starts (or joins) the single-flight upstream fetch for the country cache.
+pub fn start_refresh(state: &AppState) -> Inflight {
+    let fetch_state = state.clone();
+    state
+        .country_cache
+        .refresh(move || fetch_and_persist_countries(fetch_state))
+}
+
+/// Fetches fresh data and, on success, writes it to the snapshot file (if configured).
+///
+/// The fetch is guarded by the circuit breaker: while it is open this fails fast, and
+/// callers fall back to stale or snapshot data.
+async fn fetch_and_persist_countries(state: AppState) -> anyhow::Result<CountryData> {
+    let permit = state.country_breaker.try_acquire()?;
+
+    let data = match fetch_and_simplify_countries(state.clone()).await {
+        Ok(data) => {
+            permit.success();
+            data
+        }
+        Err(e) => {
+            permit.failure();
+            return Err(e);
+        }
+    };
+
+    // The new data is stored once this returns, so the cache still holds the previous version
+    if let CacheLookup::Fresh(previous) | CacheLookup::Stale(previous) =
+        state.country_cache.lookup()
+        && previous.data.etag != data.etag
+    {
+        let diff = CountryDiff::between(&previous.data.countries, &data.countries);
+        state
+            .country_changes
+            .record(&previous.data.etag, &data.etag, diff);
+    }
+
+    if let Some(path) = state.config.country_snapshot_path.as_deref()
+        && let Err(e) = country_snapshot::write(path, &data.records).await
+    {
+        tracing::warn!(path = %path, error = %e, "Failed to write country snapshot");
+    }
+
+    Ok(data)
+}
+
+async fn fetch_and_simplify_countries(state: AppState) -> anyhow::Result<CountryData> {
+    let parsed = state
+        .country_source
+        .load(&state.reqwest_client, state.config.country_retry_policy())
+        .await?;
+
+    let simplified = country::simplify(parsed)?;
+
+    CountryData::from_countries(simplified)
+}
@@ -0,0 +1,302 @@
+//! Typo-tolerant, diacritic-insensitive country search.
+//!
+//! The index is built once per data version (see `CountryData`) from each country's common,
+//! official and alternate names plus its codes.
+
+use serde::Serialize;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+use crate::country::Country;
+
+/// How a query matched a name. Variants are ordered best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    /// The whole name (or a code) equals the query.
+    Exact,
+    /// The name starts with the query.
+    Prefix,
+    /// A later word of the name starts with the query.
+    Word,
+    /// The query appears inside the name.
+    Substring,
+    /// Matched within the allowed edit distance.
+    Fuzzy,
+}
+
+/// A ranked search result pointing into the indexed country list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    /// Position of the country in the list the index was built from.
+    pub index: usize,
+    /// Original (unfolded) name that matched best.
+    pub matched: String,
+    pub kind: MatchKind,
+    /// Levenshtein distance to the closest name, name word or name prefix; `0` unless fuzzy.
+    pub distance: usize,
+}
+
+struct Entry {
+    country: usize,
+    text: String,
+    folded: String,
+    /// Codes only match exactly; fuzzy-matching two letters would match everything.
+    code: bool,
+}
+
+/// Folded names of every country, ready to be searched.
+pub struct SearchIndex {
+    entries: Vec<Entry>,
+}
+
+impl SearchIndex {
+    pub fn build(countries: &[Country]) -> Self {
+        let mut entries = Vec::new();
+
+        for (idx, country) in countries.iter().enumerate() {
+            let names = std::iter::once(&country.name)
+                .chain(&country.official_name)
+                .chain(&country.alt_spellings);
+            for name in names {
+                entries.push(Entry {
+                    country: idx,
+                    text: name.clone(),
+                    folded: fold(name),
+                    code: false,
+                });
+            }
+
+            for code in std::iter::once(&country.code).chain(&country.cca3) {
+                entries.push(Entry {
+                    country: idx,
+                    text: code.clone(),
+                    folded: fold(code),
+                    code: true,
+                });
+            }
+        }
+
+        Self { entries }
+    }
+
+    /// Returns the best match per country, best first, or nothing for a blank query.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query = fold(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let query_chars: Vec<char> = query.chars().collect();
+        let max_distance = max_typos(query_chars.len());
+
+        let mut best: Vec<Option<SearchHit>> = Vec::new();
+        for entry in &self.entries {
+            let Some((kind, distance)) = score(entry, &query, &query_chars, max_distance) else {
+                continue;
+            };
+
+            if best.len() <= entry.country {
+                best.resize(entry.country + 1, None);
+            }
+            let slot = &mut best[entry.country];
+            if slot
+                .as_ref()
+                .is_none_or(|hit| (kind, distance) < (hit.kind, hit.distance))
+            {
+                *slot = Some(SearchHit {
+                    index: entry.country,
+                    matched: entry.text.clone(),
+                    kind,
+                    distance,
+                });
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = best.into_iter().flatten().collect();
+        // Ties keep the index order, which is sorted by name
+        hits.sort_by_key(|hit| (hit.kind, hit.distance, hit.index));
+        hits
+    }
+}
+
+fn score(
+    entry: &Entry,
+    query: &str,
+    query_chars: &[char],
+    max_distance: usize,
+) -> Option<(MatchKind, usize)> {
+    let name = entry.folded.as_str();
+
+    if name == query {
+        return Some((MatchKind::Exact, 0));
+    }
+    if entry.code {
+        return None;
+    }
+    if name.starts_with(query) {
+        return Some((MatchKind::Prefix, 0));
+    }
+    if name.split(' ').any(|word| word.starts_with(query)) {
+        return Some((MatchKind::Word, 0));
+    }
+    if name.contains(query) {
+        return Some((MatchKind::Substring, 0));
+    }
+    if max_distance == 0 {
+        return None;
+    }
+
+    // Compare against the full name, each word, and the prefix the user may still be typing
+    let name_chars: Vec<char> = name.chars().collect();
+    let prefix_len = query_chars.len().min(name_chars.len());
+    let distance = std::iter::once(name_chars.as_slice())
+        .chain(std::iter::once(&name_chars[..prefix_len]))
+        .map(|candidate| levenshtein(query_chars, candidate))
+        .chain(name.split(' ').map(|word| {
+            let word: Vec<char> = word.chars().collect();
+            levenshtein(query_chars, &word)
+        }))
+        .min()?;
+
+    (distance <= max_distance).then_some((MatchKind::Fuzzy, distance))
+}
+
+/// Typos tolerated for a query of `len` characters.
+fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+/// Lower-cases, strips diacritics and collapses punctuation to single spaces,
+/// so "Côte d'Ivoire" and "cote d ivoire" compare equal.
+pub fn fold(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut pending_space = false;
+
+    for c in text.nfd().filter(|c| !is_combining_mark(*c)) {
+        // Letters that do not decompose into base letter + mark
+        let replacement = match c {
+            'ß' => Some("ss"),
+            'æ' | 'Æ' => Some("ae"),
+            'œ' | 'Œ' => Some("oe"),
+            'ø' | 'Ø' => Some("o"),
+            'ł' | 'Ł' => Some("l"),
+            'đ' | 'Đ' => Some("d"),
+            'þ' | 'Þ' => Some("th"),
+            'ı' => Some("i"),
+            _ => None,
+        };
+
+        if replacement.is_none() && !c.is_alphanumeric() {
+            pending_space = !out.is_empty();
+            continue;
+        }
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+        match replacement {
+            Some(s) => out.push_str(s),
+            None => out.extend(c.to_lowercase()),
+        }
+    }
+
+    out
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn country(code: &str, name: &str, official: &str, alt: &[&str]) -> Country {
+        Country {
+            code: code.into(),
+            name: name.into(),
+            official_name: Some(official.into()),
+            alt_spellings: alt.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn index() -> (Vec<Country>, SearchIndex) {
+        let countries = vec![
+            country(
+                "DE",
+                "Germany",
+                "Federal Republic of Germany",
+                &["Deutschland"],
+            ),
+            country(
+                "CI",
+                "Ivory Coast",
+                "Republic of Côte d'Ivoire",
+                &["Côte d'Ivoire"],
+            ),
+            country("AX", "Åland Islands", "Åland Islands", &["Aland"]),
+        ];
+        let index = SearchIndex::build(&countries);
+        (countries, index)
+    }
+
+    #[test]
+    fn folds_diacritics_and_punctuation() {
+        assert_eq!(fold("Côte d'Ivoire"), "cote d ivoire");
+        assert_eq!(fold("  Åland  Islands "), "aland islands");
+        assert_eq!(fold("Straße"), "strasse");
+    }
+
+    #[test]
+    fn matches_alternate_names_without_diacritics() {
+        let (countries, index) = index();
+
+        let hits = index.search("Cote");
+        assert_eq!(countries[hits[0].index].code, "CI");
+        assert_eq!(hits[0].kind, MatchKind::Prefix);
+        assert_eq!(hits[0].matched, "Côte d'Ivoire");
+
+        let hits = index.search("deutsch");
+        assert_eq!(countries[hits[0].index].code, "DE");
+
+        let hits = index.search("ci");
+        assert_eq!(hits[0].kind, MatchKind::Exact);
+    }
+
+    #[test]
+    fn tolerates_typos_by_edit_distance() {
+        let (countries, index) = index();
+
+        let hits = index.search("Germny");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(countries[hits[0].index].code, "DE");
+        assert_eq!((hits[0].kind, hits[0].distance), (MatchKind::Fuzzy, 1));
+
+        assert!(index.search("xyzzy").is_empty());
+        assert!(index.search("   ").is_empty());
+    }
+
+    #[test]
+    fn computes_levenshtein_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(levenshtein(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(levenshtein(&chars(""), &chars("abc")), 3);
+        assert_eq!(levenshtein(&chars("abc"), &chars("abc")), 0);
+    }
+}
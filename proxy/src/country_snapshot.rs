@@ -0,0 +1,47 @@
+use anyhow::Context;
+use std::path::Path;
+use tokio::fs;
+
+use crate::country_cache::CountryData;
+
+/// Reads the last good simplified country payload from disk.
+///
+/// Returns `Ok(None)` when no snapshot has been written yet. A file that is not a
+/// JSON array is treated as an error so a corrupt snapshot is never served.
+pub async fn read(path: &str) -> anyhow::Result<Option<CountryData>> {
+    let bytes = match fs::read(path).await {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read country snapshot {path}"));
+        }
+    };
+
+    let data = CountryData::from_records(&bytes)
+        .with_context(|| format!("Invalid country snapshot {path}"))?;
+
+    Ok(Some(data))
+}
+
+/// Atomically replaces the snapshot on disk with `body` (a dataset's `records`).
+///
+/// Writes to a sibling temp file first and renames it into place so readers never
+/// observe a partially written snapshot.
+pub async fn write(path: &str, body: &[u8]) -> anyhow::Result<()> {
+    let target = Path::new(path);
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create snapshot directory {}", parent.display()))?;
+    }
+
+    let tmp = target.with_extension("tmp");
+    fs::write(&tmp, body)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, target)
+        .await
+        .with_context(|| format!("Failed to move snapshot into place at {path}"))?;
+
+    Ok(())
+}
@@ -0,0 +1,299 @@
+use anyhow::Context;
+use metrics::counter;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value, error::Category};
+use std::collections::HashMap;
+
+use crate::retry::RetryPolicy;
+
+/// restcountries-shaped fixture compiled into the binary for offline use.
+const EMBEDDED_FIXTURE: &str = include_str!("../fixtures/restcountries.json");
+
+/// Where raw (restcountries-shaped) country data is loaded from, selected in proxy.ron.
+///
+/// Every source feeds the same simplification code, so dev machines and CI can run
+/// without network access and still exercise the real pipeline.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub enum CountrySource {
+    /// Remote restcountries-compatible URLs, fetched with timeouts and retries.
+    ///
+    /// restcountries caps `fields=` at 10 per request, so extra URLs can fetch more fields;
+    /// their records are merged into those of the first URL by `cca3`. A single URL may also
+    /// be written without the list, as in `Http("https://...")`.
+    Http(#[serde(deserialize_with = "one_or_many")] Vec<String>),
+    /// Local JSON file in the restcountries shape.
+    File(String),
+    /// Fixture compiled into the binary.
+    Embedded,
+}
+
+/// Reads either one string or a list of strings.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(url) => vec![url],
+        OneOrMany::Many(urls) => urls,
+    })
+}
+
+impl CountrySource {
+    /// Short description for logs.
+    pub fn describe(&self) -> String {
+        match self {
+            CountrySource::Http(urls) => format!("http:{}", urls.join(" + ")),
+            CountrySource::File(path) => format!("file:{path}"),
+            CountrySource::Embedded => "embedded".to_string(),
+        }
+    }
+
+    /// Loads the raw country records from this source.
+    pub async fn load(
+        &self,
+        client: &reqwest::Client,
+        policy: RetryPolicy,
+    ) -> anyhow::Result<Vec<Value>> {
+        match self {
+            CountrySource::Http(urls) => {
+                let mut records = Vec::new();
+                for (idx, url) in urls.iter().enumerate() {
+                    let fetched = fetch_with_retries(client, url, policy).await?;
+                    if idx == 0 {
+                        records = fetched;
+                    } else {
+                        merge_records(&mut records, fetched);
+                    }
+                }
+                Ok(records)
+            }
+            CountrySource::File(path) => {
+                let bytes = tokio::fs::read(path)
+                    .await
+                    .with_context(|| format!("Failed to read country source file {path}"))?;
+                serde_json::from_slice(&bytes)
+                    .with_context(|| format!("Failed to parse country source file {path}"))
+            }
+            CountrySource::Embedded => serde_json::from_str(EMBEDDED_FIXTURE)
+                .context("Failed to parse embedded country fixture"),
+        }
+    }
+}
+
+/// Adds the fields of each `extra` record to the `base` record with the same `cca3`,
+/// keeping the base value when both have a field.
+fn merge_records(base: &mut [Value], extra: Vec<Value>) {
+    let mut by_cca3: HashMap<String, Map<String, Value>> = extra
+        .into_iter()
+        .filter_map(|record| match record {
+            Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .filter_map(|map| Some((map.get("cca3")?.as_str()?.to_string(), map)))
+        .collect();
+
+    for record in base {
+        let Some(cca3) = record.get("cca3").and_then(Value::as_str) else {
+            continue;
+        };
+        let Some(extra) = by_cca3.remove(cca3) else {
+            continue;
+        };
+        if let Value::Object(map) = record {
+            for (key, value) in extra {
+                map.entry(key).or_insert(value);
+            }
+        }
+    }
+}
+
+/// Fetches the raw upstream payload, retrying network errors and 5xx responses with
+/// exponential backoff.
+async fn fetch_with_retries(
+    client: &reqwest::Client,
+    url: &str,
+    policy: RetryPolicy,
+) -> anyhow::Result<Vec<Value>> {
+    let mut retry = 0;
+
+    loop {
+        match fetch(client, url).await {
+            Ok(parsed) => return Ok(parsed),
+            Err(FetchError::Transient(e)) if retry < policy.max_retries => {
+                retry += 1;
+                let delay = policy.backoff(retry);
+                tracing::warn!(
+                    retry,
+                    max_retries = policy.max_retries,
+                    delay_ms = delay.as_millis() as u64,
+                    error = %e,
+                    "Retrying country fetch"
+                );
+                counter!("country_fetch_retries_total").increment(1);
+                tokio::time::sleep(delay).await;
+            }
+            Err(FetchError::Transient(e) | FetchError::Permanent(e)) => return Err(e),
+        }
+    }
+}
+
+/// Upstream failure, classified by whether retrying could help.
+enum FetchError {
+    Transient(anyhow::Error),
+    Permanent(anyhow::Error),
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<Vec<Value>, FetchError> {
+    let resp = client.get(url).send().await.map_err(|e| {
+        // A malformed URL or a redirect loop fails the same way on every attempt
+        let permanent = e.is_builder() || e.is_redirect();
+        let e = anyhow::Error::new(e).context("Failed to fetch countries upstream");
+        if permanent {
+            FetchError::Permanent(e)
+        } else {
+            FetchError::Transient(e)
+        }
+    })?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        tracing::error!(status = %status, "Upstream returned non-200");
+        let e = anyhow::anyhow!("Upstream returned non-200 status: {status}");
+        return Err(if status.is_server_error() {
+            FetchError::Transient(e)
+        } else {
+            FetchError::Permanent(e)
+        });
+    }
+
+    // Reading the body can time out or be cut off mid-transfer; both are worth a retry
+    let body = resp
+        .bytes()
+        .await
+        .context("Failed to read upstream response body")
+        .map_err(FetchError::Transient)?;
+
+    serde_json::from_slice(&body).map_err(|e| {
+        let transient = matches!(e.classify(), Category::Io | Category::Eof);
+        let e = anyhow::Error::new(e).context("Failed to parse upstream JSON");
+        // Truncated JSON may be complete on the next attempt; malformed or mis-shaped JSON won't
+        if transient {
+            FetchError::Transient(e)
+        } else {
+            FetchError::Permanent(e)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Serves each body from `bodies` at `/{index}`; `None` sends headers and then stalls.
+    async fn serve(bodies: Vec<Option<&'static str>>) -> u16 {
+        use axum::{Router, body::Body, extract::Path, routing::get};
+        use futures_util::{StreamExt, stream};
+
+        let app = Router::new().route(
+            "/{index}",
+            get(move |Path(index): Path<usize>| async move {
+                match bodies[index] {
+                    Some(body) => Body::from(body),
+                    None => Body::from_stream(
+                        stream::once(async { Ok::<_, std::io::Error>("[") })
+                            .chain(stream::pending()),
+                    ),
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        port
+    }
+
+    #[tokio::test]
+    async fn retries_unreadable_bodies_but_not_bad_json() {
+        let port = serve(vec![
+            None,
+            Some(r#"[{"cca3": "FRA"}"#),
+            Some(r#"{"cca3": "FRA"}"#),
+            Some("<html>"),
+            Some(r#"[{"cca3": "FRA"}]"#),
+        ])
+        .await;
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let url = |index: usize| format!("http://127.0.0.1:{port}/{index}");
+
+        // A body that stops arriving times out; truncated JSON may be complete next time
+        assert!(matches!(
+            fetch(&client, &url(0)).await,
+            Err(FetchError::Transient(_))
+        ));
+        assert!(matches!(
+            fetch(&client, &url(1)).await,
+            Err(FetchError::Transient(_))
+        ));
+        // Well-formed JSON of the wrong shape and non-JSON bodies won't fix themselves
+        assert!(matches!(
+            fetch(&client, &url(2)).await,
+            Err(FetchError::Permanent(_))
+        ));
+        assert!(matches!(
+            fetch(&client, &url(3)).await,
+            Err(FetchError::Permanent(_))
+        ));
+        assert_eq!(
+            fetch(&client, &url(4)).await.ok().unwrap(),
+            [json!({"cca3": "FRA"})]
+        );
+
+        // Neither will a URL that cannot be parsed
+        assert!(matches!(
+            fetch(&client, "not a url").await,
+            Err(FetchError::Permanent(_))
+        ));
+    }
+
+    #[test]
+    fn accepts_one_or_many_http_urls() {
+        let parse = |ron: &str| ron::de::from_str::<CountrySource>(ron).unwrap();
+        assert_eq!(
+            parse(r#"Http("https://a")"#),
+            CountrySource::Http(vec!["https://a".into()])
+        );
+        assert_eq!(
+            parse(r#"Http(["https://a", "https://b"])"#),
+            CountrySource::Http(vec!["https://a".into(), "https://b".into()])
+        );
+        assert_eq!(parse("Embedded"), CountrySource::Embedded);
+    }
+
+    #[test]
+    fn merges_extra_fields_by_cca3() {
+        let mut base = vec![
+            json!({"cca3": "FRA", "name": "France"}),
+            json!({"cca3": "DEU", "name": "Germany"}),
+        ];
+        let extra = vec![
+            json!({"cca3": "FRA", "capital": ["Paris"], "name": "ignored"}),
+            json!({"cca3": "ESP", "capital": ["Madrid"]}),
+        ];
+
+        merge_records(&mut base, extra);
+
+        assert_eq!(
+            base[0],
+            json!({"cca3": "FRA", "name": "France", "capital": ["Paris"]})
+        );
+        assert_eq!(base[1], json!({"cca3": "DEU", "name": "Germany"}));
+    }
+}
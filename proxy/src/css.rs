@@ -0,0 +1,432 @@
+//! Minimal CSS tokenizer and block parser, enough to read daisyUI `@plugin` blocks.
+//!
+//! It follows the CSS Syntax tokenization rules that matter for theme files: comments are
+//! dropped, strings are unescaped, and braces inside strings, comments or nested blocks do
+//! not end the enclosing block. Selectors and values are kept as text rather than parsed.
+
+/// A lexical token; `Other` covers identifiers, numbers, hashes and any other delimiter run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token<'a> {
+    Whitespace,
+    /// `@name`, without the `@`.
+    AtKeyword(&'a str),
+    /// Quoted string with escapes resolved and quotes removed.
+    String(String),
+    Colon,
+    Semicolon,
+    OpenBrace,
+    CloseBrace,
+    OpenParen,
+    CloseParen,
+    Other(&'a str),
+}
+
+/// Splits `css` into tokens, skipping comments. Unterminated strings and comments run to
+/// the end of the line and input respectively, as browsers do.
+pub fn tokenize(css: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = css.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            '/' if css[start..].starts_with("/*") => {
+                let end = css[start + 2..]
+                    .find("*/")
+                    .map_or(css.len(), |i| start + 2 + i + 2);
+                while chars.next_if(|(i, _)| *i < end).is_some() {}
+                continue;
+            }
+            c if c.is_whitespace() => {
+                while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+                Token::Whitespace
+            }
+            '"' | '\'' => Token::String(string_body(&mut chars, c)),
+            '@' => {
+                let mut end = start + 1;
+                while let Some((i, c)) = chars.next_if(|(_, c)| is_name_char(*c)) {
+                    end = i + c.len_utf8();
+                }
+                if end == start + 1 {
+                    Token::Other("@")
+                } else {
+                    Token::AtKeyword(&css[start + 1..end])
+                }
+            }
+            ':' => Token::Colon,
+            ';' => Token::Semicolon,
+            '{' => Token::OpenBrace,
+            '}' => Token::CloseBrace,
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            _ => {
+                let mut end = start + c.len_utf8();
+                let mut escaped = c == '\\';
+                while let Some((i, c)) = chars
+                    .next_if(|(i, c)| escaped || !(is_special(*c) || css[*i..].starts_with("/*")))
+                {
+                    escaped = !escaped && c == '\\';
+                    end = i + c.len_utf8();
+                }
+                Token::Other(&css[start..end])
+            }
+        };
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+fn is_special(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '"' | '\'' | '@' | ':' | ';' | '{' | '}' | '(' | ')')
+}
+
+/// Reads a string after its opening `quote`, resolving `\` escapes.
+fn string_body(chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>, quote: char) -> String {
+    let mut out = String::new();
+
+    while let Some((_, c)) = chars.next_if(|(_, c)| *c != '\n') {
+        match c {
+            c if c == quote => break,
+            '\\' => match chars.next() {
+                // An escaped newline continues the string onto the next line
+                Some((_, '\n')) | None => {}
+                Some((_, h)) if h.is_ascii_hexdigit() => {
+                    let mut hex = String::from(h);
+                    while hex.len() < 6
+                        && let Some((_, h)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit())
+                    {
+                        hex.push(h);
+                    }
+                    // One whitespace character after a hex escape belongs to it
+                    chars.next_if(|(_, c)| c.is_whitespace());
+                    let code = u32::from_str_radix(&hex, 16).unwrap_or(0xFFFD);
+                    out.push(
+                        char::from_u32(code)
+                            .filter(|c| *c != '\0')
+                            .unwrap_or('\u{FFFD}'),
+                    );
+                }
+                Some((_, other)) => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// A `name: value` declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    pub name: String,
+    /// The value with whitespace collapsed and comments removed. A value that is a single
+    /// string is returned unquoted; strings within longer values keep double quotes.
+    pub value: String,
+}
+
+/// A rule or at-rule with a `{}` body.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Block {
+    /// At-rule name without `@`; `None` for style rules.
+    pub at_rule: Option<String>,
+    /// Selector or at-rule prelude, as text (strings in double quotes).
+    pub prelude: String,
+    pub declarations: Vec<Declaration>,
+    /// Nested rules and at-rules such as `@media`.
+    pub blocks: Vec<Block>,
+}
+
+impl Block {
+    /// Looks up the last declaration of `name`, as the cascade would.
+    pub fn declaration(&self, name: &str) -> Option<&str> {
+        self.declarations
+            .iter()
+            .rev()
+            .find(|d| d.name == name)
+            .map(|d| d.value.as_str())
+    }
+}
+
+/// Parses a stylesheet into its top-level blocks. Statements without a body (such as
+/// `@import`) are skipped, as are stray closing braces.
+pub fn parse(css: &str) -> Vec<Block> {
+    let tokens = tokenize(css);
+    let mut pos = 0;
+    let mut blocks = Vec::new();
+
+    while pos < tokens.len() {
+        match parse_item(&tokens, &mut pos) {
+            Item::Block(block) => blocks.push(block),
+            Item::Declaration(_) | Item::End | Item::Empty => {}
+        }
+    }
+
+    blocks
+}
+
+/// Every `@plugin "<plugin>" { ... }` block, including ones nested in other blocks.
+pub fn plugin_blocks(css: &str, plugin: &str) -> Vec<Block> {
+    fn collect(blocks: Vec<Block>, prelude: &str, out: &mut Vec<Block>) {
+        for mut block in blocks {
+            let children = std::mem::take(&mut block.blocks);
+            if block.at_rule.as_deref() == Some("plugin") && block.prelude == prelude {
+                block.blocks = children;
+                out.push(block);
+            } else {
+                collect(children, prelude, out);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    collect(parse(css), &quote(plugin), &mut out);
+    out
+}
+
+enum Item {
+    Block(Block),
+    Declaration(Declaration),
+    /// A closing brace ended the enclosing block.
+    End,
+    /// A lone `;`, or a statement that is not a declaration.
+    Empty,
+}
+
+/// Reads one declaration, statement or block starting at `pos`.
+fn parse_item(tokens: &[Token<'_>], pos: &mut usize) -> Item {
+    let start = *pos;
+    let mut paren_depth = 0usize;
+
+    while let Some(token) = tokens.get(*pos) {
+        *pos += 1;
+        match token {
+            Token::OpenParen => paren_depth += 1,
+            Token::CloseParen => paren_depth = paren_depth.saturating_sub(1),
+            Token::Semicolon if paren_depth == 0 => {
+                return declaration(&tokens[start..*pos - 1])
+                    .map_or(Item::Empty, Item::Declaration);
+            }
+            Token::OpenBrace => return Item::Block(block(&tokens[start..*pos - 1], tokens, pos)),
+            Token::CloseBrace => {
+                // A final declaration may omit its semicolon
+                *pos -= 1;
+                if *pos > start {
+                    return declaration(&tokens[start..*pos])
+                        .map_or(Item::Empty, Item::Declaration);
+                }
+                *pos += 1;
+                return Item::End;
+            }
+            _ => {}
+        }
+    }
+
+    declaration(&tokens[start..]).map_or(Item::Empty, Item::Declaration)
+}
+
+fn block(prelude: &[Token<'_>], tokens: &[Token<'_>], pos: &mut usize) -> Block {
+    let (at_rule, prelude) = match prelude
+        .iter()
+        .position(|t| *t != Token::Whitespace)
+        .map(|i| (i, &prelude[i]))
+    {
+        Some((i, Token::AtKeyword(name))) => (Some(name.to_string()), &prelude[i + 1..]),
+        _ => (None, prelude),
+    };
+    let mut block = Block {
+        at_rule,
+        prelude: text(prelude),
+        ..Default::default()
+    };
+
+    while *pos < tokens.len() {
+        match parse_item(tokens, pos) {
+            Item::Block(child) => block.blocks.push(child),
+            Item::Declaration(declaration) => block.declarations.push(declaration),
+            Item::End => break,
+            Item::Empty => {}
+        }
+    }
+
+    block
+}
+
+fn declaration(tokens: &[Token<'_>]) -> Option<Declaration> {
+    let colon = tokens.iter().position(|t| *t == Token::Colon)?;
+    let name = text(&tokens[..colon]);
+    if name.is_empty() || name.contains(' ') {
+        return None;
+    }
+
+    let value_tokens: Vec<&Token<'_>> = tokens[colon + 1..]
+        .iter()
+        .filter(|t| **t != Token::Whitespace)
+        .collect();
+    let value = match value_tokens.as_slice() {
+        [Token::String(s)] => s.clone(),
+        _ => text(&tokens[colon + 1..]),
+    };
+
+    Some(Declaration { name, value })
+}
+
+/// Renders tokens back to text with whitespace collapsed to single spaces.
+fn text(tokens: &[Token<'_>]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        match token {
+            // Comments between spaces leave two whitespace tokens in a row
+            Token::Whitespace if out.ends_with(' ') => {}
+            Token::Whitespace => out.push(' '),
+            Token::AtKeyword(name) => {
+                out.push('@');
+                out.push_str(name);
+            }
+            Token::String(s) => out.push_str(&quote(s)),
+            Token::Colon => out.push(':'),
+            Token::Semicolon => out.push(';'),
+            Token::OpenBrace => out.push('{'),
+            Token::CloseBrace => out.push('}'),
+            Token::OpenParen => out.push('('),
+            Token::CloseParen => out.push(')'),
+            Token::Other(s) => out.push_str(s),
+        }
+    }
+    out.trim().to_string()
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes() {
+        use Token::*;
+
+        let cases: &[(&str, &[Token])] = &[
+            ("a{}", &[Other("a"), OpenBrace, CloseBrace]),
+            ("/* } \" */x", &[Other("x")]),
+            ("a/**/b", &[Other("a"), Other("b")]),
+            ("/* unterminated", &[]),
+            (r#""a\"b""#, &[String("a\"b".into())]),
+            (r"'it\'s'", &[String("it's".into())]),
+            (r#""\41 B""#, &[String("AB".into())]),
+            ("\"a\\\nb\"", &[String("ab".into())]),
+            (
+                "\"open\nx",
+                &[String("open".into()), Whitespace, Other("x")],
+            ),
+            (
+                "@media (x:1)",
+                &[
+                    AtKeyword("media"),
+                    Whitespace,
+                    OpenParen,
+                    Other("x"),
+                    Colon,
+                    Other("1"),
+                    CloseParen,
+                ],
+            ),
+            (r"a\;b;", &[Other(r"a\;b"), Semicolon]),
+        ];
+
+        for (css, expected) in cases {
+            assert_eq!(tokenize(css), *expected, "input: {css:?}");
+        }
+    }
+
+    fn declarations(block: &Block) -> Vec<(&str, &str)> {
+        block
+            .declarations
+            .iter()
+            .map(|d| (d.name.as_str(), d.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_plugin_blocks() {
+        // (label, css, expected declarations of the single theme block)
+        type Case = (
+            &'static str,
+            &'static str,
+            &'static [(&'static str, &'static str)],
+        );
+        let cases: &[Case] = &[
+            (
+                "simple",
+                r#"@plugin "daisyui/theme" { name: "a"; --color-primary: red; }"#,
+                &[("name", "a"), ("--color-primary", "red")],
+            ),
+            (
+                "missing final semicolon",
+                r#"@plugin "daisyui/theme" { name: "a"; --radius-box: 1rem }"#,
+                &[("name", "a"), ("--radius-box", "1rem")],
+            ),
+            (
+                "comments with quotes and braces",
+                r#"@plugin "daisyui/theme" {
+                    /* it's a "theme" } */
+                    name: "a"; /* { */
+                    --color-primary: oklch(50% /* mid */ 0.1 200);
+                }"#,
+                &[("name", "a"), ("--color-primary", "oklch(50% 0.1 200)")],
+            ),
+            (
+                "escaped strings",
+                r#"@plugin "daisyui/theme" { name: "a \"b\" }"; --x: 'c\'d'; }"#,
+                &[("name", "a \"b\" }"), ("--x", "c'd")],
+            ),
+            (
+                "nested blocks",
+                r#"@plugin "daisyui/theme" {
+                    name: "a";
+                    @media (prefers-color-scheme: dark) { --color-primary: black; }
+                    &:hover { color: red; }
+                    --color-primary: white;
+                }"#,
+                &[("name", "a"), ("--color-primary", "white")],
+            ),
+            (
+                "semicolon inside parentheses",
+                r#"@plugin "daisyui/theme" { --noise: url(data:image/png;base64,AA); }"#,
+                &[("--noise", "url(data:image/png;base64,AA)")],
+            ),
+        ];
+
+        for (label, css, expected) in cases {
+            let blocks = plugin_blocks(css, "daisyui/theme");
+            assert_eq!(blocks.len(), 1, "{label}");
+            assert_eq!(declarations(&blocks[0]), *expected, "{label}");
+        }
+    }
+
+    #[test]
+    fn finds_only_matching_plugins() {
+        let css = r#"
+            @import "tailwindcss";
+            @plugin "daisyui" { themes: light --default, dark; }
+            @plugin 'daisyui/theme' { name: "single-quoted"; }
+            @layer base { @plugin "daisyui/theme" { name: "nested"; } }
+            .x { color: red; } }
+            @plugin "daisyui/theme" { name: "last"; }
+        "#;
+
+        let blocks = plugin_blocks(css, "daisyui/theme");
+        let names: Vec<Option<&str>> = blocks.iter().map(|b| b.declaration("name")).collect();
+        assert_eq!(names, [Some("single-quoted"), Some("nested"), Some("last")]);
+
+        let nested = &parse(r#"@plugin "daisyui/theme" { @media print { a { b: c; } } }"#)[0];
+        assert_eq!(nested.blocks[0].at_rule.as_deref(), Some("media"));
+        assert_eq!(nested.blocks[0].prelude, "print");
+        assert_eq!(nested.blocks[0].blocks[0].declaration("b"), Some("c"));
+    }
+}
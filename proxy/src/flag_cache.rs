@@ -0,0 +1,278 @@
+//! On-disk cache for flag images served by `/api/flags/{code}.{png|svg}`.
+//!
+//! Each flag is fetched from the configured upstream once and kept in `flag_cache_dir`, so
+//! browsers never contact the flag CDN and cached flags keep working offline.
+
+use anyhow::Context;
+use axum::body::Bytes;
+use std::{path::PathBuf, sync::Arc};
+use tokio::fs;
+
+use crate::single_flight::{SharedOutcome, SingleFlight};
+
+/// Largest flag image accepted from upstream.
+const MAX_FLAG_BYTES: usize = 1024 * 1024;
+
+/// Outcome of a flag fetch, shared between every caller waiting on it.
+pub type FlagOutcome = SharedOutcome<Bytes>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlagFormat {
+    Png,
+    Svg,
+}
+
+impl FlagFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            FlagFormat::Png => "png",
+            FlagFormat::Svg => "svg",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            FlagFormat::Png => "image/png",
+            FlagFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+/// Splits a requested file name such as `fr.png` into a lower-case two-letter code and format.
+pub fn parse_file_name(file: &str) -> Option<(String, FlagFormat)> {
+    let (code, ext) = file.rsplit_once('.')?;
+    let format = match ext.to_ascii_lowercase().as_str() {
+        "png" => FlagFormat::Png,
+        "svg" => FlagFormat::Svg,
+        _ => return None,
+    };
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some((code.to_ascii_lowercase(), format))
+}
+
+/// Path `/api/country` responses use for a flag, in place of the upstream CDN URL.
+pub fn proxied_url(code: &str, format: FlagFormat) -> String {
+    format!(
+        "/api/flags/{}.{}",
+        code.to_ascii_lowercase(),
+        format.extension()
+    )
+}
+
+/// Flags on disk, plus the upstream fetches running for flags that are not there yet.
+///
+/// Fetches are single-flight per code and format, through [`SingleFlight`]: concurrent misses
+/// for the same flag share one upstream request and one file write.
+pub struct FlagCache {
+    dir: PathBuf,
+    png_url: String,
+    svg_url: String,
+    flights: SingleFlight<(String, FlagFormat), Bytes>,
+}
+
+impl FlagCache {
+    /// `png_url` and `svg_url` are upstream URL templates in which `{code}` is replaced by the
+    /// lower-case two-letter code.
+    pub fn new(dir: impl Into<PathBuf>, png_url: &str, svg_url: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            png_url: png_url.to_string(),
+            svg_url: svg_url.to_string(),
+            flights: SingleFlight::new(),
+        }
+    }
+
+    fn upstream_url(&self, code: &str, format: FlagFormat) -> String {
+        let template = match format {
+            FlagFormat::Png => &self.png_url,
+            FlagFormat::Svg => &self.svg_url,
+        };
+        template.replace("{code}", code)
+    }
+
+    fn path(&self, code: &str, format: FlagFormat) -> PathBuf {
+        self.dir.join(format!("{code}.{}", format.extension()))
+    }
+
+    /// Returns the flag from disk, or `None` if it has not been fetched yet.
+    ///
+    /// `code` must already be a two-letter code from [`parse_file_name`], since it becomes
+    /// part of a file name.
+    pub async fn cached(&self, code: &str, format: FlagFormat) -> anyhow::Result<Option<Bytes>> {
+        let path = self.path(code, format);
+        match fs::read(&path).await {
+            Ok(bytes) => Ok(Some(Bytes::from(bytes))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Fetches a flag from upstream and stores it, or joins the fetch already running for it.
+    ///
+    /// The boolean is `true` when this caller joined a fetch started by someone else. The
+    /// fetch runs on its own task so that a cancelled request cannot abandon the others.
+    pub async fn fetch(
+        self: &Arc<Self>,
+        client: &reqwest::Client,
+        code: &str,
+        format: FlagFormat,
+    ) -> (FlagOutcome, bool) {
+        let flight = self.flights.run((code.to_string(), format), || {
+            let cache = Arc::clone(self);
+            let client = client.clone();
+            let code = code.to_string();
+            async move { cache.download(&client, &code, format).await }
+        });
+        let coalesced = flight.coalesced;
+        (flight.wait().await, coalesced)
+    }
+
+    async fn download(
+        &self,
+        client: &reqwest::Client,
+        code: &str,
+        format: FlagFormat,
+    ) -> anyhow::Result<Bytes> {
+        // A fetch that finished just before this one started has already stored the flag
+        if let Some(bytes) = self.cached(code, format).await? {
+            return Ok(bytes);
+        }
+
+        let url = self.upstream_url(code, format);
+        let resp = client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch flag {url}"))?;
+        let status = resp.status();
+        if !status.is_success() {
+            anyhow::bail!("Flag upstream returned {status} for {url}");
+        }
+        if resp
+            .content_length()
+            .is_some_and(|len| len > MAX_FLAG_BYTES as u64)
+        {
+            anyhow::bail!("Flag {url} exceeds {MAX_FLAG_BYTES} bytes");
+        }
+        let bytes = resp
+            .bytes()
+            .await
+            .with_context(|| format!("Failed to read flag {url}"))?;
+        if bytes.len() > MAX_FLAG_BYTES {
+            anyhow::bail!("Flag {url} exceeds {MAX_FLAG_BYTES} bytes");
+        }
+
+        // Write to a temp file and rename, so readers never see a partial image
+        let path = self.path(code, format);
+        fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create flag directory {}", self.dir.display()))?;
+        let tmp = self.dir.join(format!(
+            "{code}.{}.{}.tmp",
+            format.extension(),
+            rand::random::<u32>()
+        ));
+        fs::write(&tmp, &bytes)
+            .await
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &path)
+            .await
+            .with_context(|| format!("Failed to move flag into place at {}", path.display()))?;
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flag_file_names() {
+        assert_eq!(
+            parse_file_name("FR.png"),
+            Some(("fr".to_string(), FlagFormat::Png))
+        );
+        assert_eq!(
+            parse_file_name("jp.SVG"),
+            Some(("jp".to_string(), FlagFormat::Svg))
+        );
+        assert_eq!(parse_file_name("fr.gif"), None);
+        assert_eq!(parse_file_name("fra.png"), None);
+        assert_eq!(parse_file_name("..png"), None);
+        assert_eq!(parse_file_name("fr"), None);
+    }
+
+    #[test]
+    fn fills_upstream_templates() {
+        let cache = FlagCache::new(
+            "flags",
+            "https://flagcdn.com/w320/{code}.png",
+            "https://flagcdn.com/{code}.svg",
+        );
+        assert_eq!(
+            cache.upstream_url("fr", FlagFormat::Png),
+            "https://flagcdn.com/w320/fr.png"
+        );
+        assert_eq!(
+            cache.upstream_url("fr", FlagFormat::Svg),
+            "https://flagcdn.com/fr.svg"
+        );
+        assert_eq!(proxied_url("FR", FlagFormat::Svg), "/api/flags/fr.svg");
+    }
+
+    #[tokio::test]
+    async fn coalesces_concurrent_misses() {
+        use axum::{Router, routing::get};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&fetches);
+        let app = Router::new().route(
+            "/{file}",
+            get(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                "PNG"
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dir = std::env::temp_dir().join(format!("flag-cache-{}", std::process::id()));
+        let cache = Arc::new(FlagCache::new(
+            &dir,
+            &format!("http://127.0.0.1:{port}/{{code}}.png"),
+            &format!("http://127.0.0.1:{port}/{{code}}.svg"),
+        ));
+        let client = reqwest::Client::new();
+
+        let results = futures_util::future::join_all(
+            (0..8).map(|_| cache.fetch(&client, "fr", FlagFormat::Png)),
+        )
+        .await;
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            results.iter().filter(|(_, coalesced)| !coalesced).count(),
+            1
+        );
+        for (outcome, _) in results {
+            assert_eq!(&outcome.unwrap()[..], b"PNG");
+        }
+        assert_eq!(
+            cache
+                .cached("fr", FlagFormat::Png)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(&b"PNG"[..])
+        );
+        assert_eq!(cache.cached("fr", FlagFormat::Svg).await.unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
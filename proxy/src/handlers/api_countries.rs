@@ -1,90 +1,566 @@
-use anyhow::Context;
 use axum::{
+    Json,
     body::Body as AxumBody,
-    extract::State,
-    http::{Response, StatusCode, header},
+    extract::{Path, Query, RawQuery, State},
+    http::{HeaderMap, Response, StatusCode, header},
     response::IntoResponse,
 };
+use serde::Deserialize;
+use serde_json::{Value, json};
 
-use crate::state::AppState;
-
-/// Fetches countries from restcountries.com via reqwest, simplifies the JSON, and returns it.
-pub async fn api_countries(State(state): State<AppState>) -> impl IntoResponse {
-    match fetch_and_simplify_countries(state).await {
-        Ok(resp_body) => Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(AxumBody::from(resp_body))
-            .unwrap()
-            .into_response(),
+use crate::{
+    country::{COUNTRY_FIELDS, COUNTRY_SCHEMA_VERSION, Country, FieldSelection, UnknownFields},
+    country_cache::{CacheLookup, CachedCountries, DataSource},
+    country_export::{self, ExportFormat},
+    country_refresher::start_refresh,
+    country_snapshot, http_cache,
+    locale::NameLocale,
+    state::AppState,
+};
+
+/// `Vary` for the list, which negotiates both the export format and the name language.
+const LIST_VARY: &str = "Accept, Accept-Language";
+/// `Vary` for single-country responses, which only negotiate the name language.
+const LOCALIZED_VARY: &str = "Accept-Language";
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Name,
+    Population,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query parameters for `/api/country`, mirroring the frontend `useCountryFilters` hook.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct CountryQuery {
+    /// Exact region match; `All` (or absent) disables the filter.
+    pub region: Option<String>,
+    /// Case-insensitive substring match on name or code.
+    pub q: Option<String>,
+    pub sort: Option<SortKey>,
+    pub order: Option<SortOrder>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// `format=` override for content negotiation, kept apart from the filters so an explicit
+/// format does not disable the pre-serialized fast path.
+#[derive(Debug, Default, Deserialize)]
+pub struct FormatQuery {
+    pub format: Option<ExportFormat>,
+}
+
+/// `lang=` override for `Accept-Language`, shared by the list and single-country endpoints.
+#[derive(Debug, Default, Deserialize)]
+pub struct LangQuery {
+    pub lang: Option<String>,
+}
+
+/// `fields=` selector shared by the list and single-country endpoints.
+#[derive(Debug, Default, Deserialize)]
+pub struct FieldsQuery {
+    pub fields: Option<String>,
+}
+
+/// `400 Bad Request` listing the unknown and the allowed field names.
+fn unknown_fields_response(e: UnknownFields) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "Unknown fields",
+            "fields": e.0,
+            "allowed": COUNTRY_FIELDS,
+        })),
+    )
+        .into_response()
+}
+
+impl CountryQuery {
+    /// Filters and sorts `countries`, returning the total match count and the requested page.
+    ///
+    /// Names are sorted with `locale`'s collation rules.
+    pub fn apply<'a>(
+        &self,
+        countries: &'a [Country],
+        locale: &NameLocale,
+    ) -> (usize, Vec<&'a Country>) {
+        let region = self.region.as_deref().filter(|r| *r != "All");
+        let q = self
+            .q
+            .as_deref()
+            .map(|q| q.trim().to_lowercase())
+            .filter(|q| !q.is_empty());
+
+        let mut list: Vec<&Country> = countries
+            .iter()
+            .filter(|c| region.is_none_or(|r| c.region == r))
+            .filter(|c| {
+                q.as_deref().is_none_or(|q| {
+                    c.name.to_lowercase().contains(q) || c.code.to_lowercase().contains(q)
+                })
+            })
+            .collect();
+
+        match self.sort.unwrap_or_default() {
+            SortKey::Name => list.sort_by(|a, b| locale.compare(&a.name, &b.name)),
+            SortKey::Population => list.sort_by_key(|c| c.population),
+        }
+
+        if self.order.unwrap_or_default() == SortOrder::Desc {
+            list.reverse();
+        }
+
+        let total = list.len();
+        let page = list
+            .into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+
+        (total, page)
+    }
+}
+
+/// Serves the simplified country list, loading from the configured source only when the cache is empty.
+///
+/// Supports `region`, `q`, `sort`, `order`, `limit` and `offset` query parameters. The number
+/// of matches before pagination is returned in `X-Total-Count`, and `X-Data-Source` reports
+/// whether the payload came from upstream or from the on-disk snapshot. `X-Schema-Version`
+/// identifies the shape of each record.
+///
+/// The representation is negotiated from `format=json|csv|ndjson` or, failing that, the
+/// `Accept` header. Filtered and non-JSON responses are streamed in chunks. Records carry the
+/// default fields; `fields=` picks the fields of each record (or CSV row) instead, which is the
+/// only way to receive the detail fields such as `capital` or `translations`.
+///
+/// Names are translated into the language chosen by `lang=` or `Accept-Language` (falling back
+/// to English per country), then filtered and sorted with that language's collation. The
+/// chosen language is sent in `Content-Language`.
+///
+/// Responses carry a strong ETag derived from the data version, query string, format and
+/// language, and a matching `If-None-Match` yields `304 Not Modified`.
+pub async fn api_countries(
+    State(state): State<AppState>,
+    Query(query): Query<CountryQuery>,
+    Query(FormatQuery { format }): Query<FormatQuery>,
+    Query(fields): Query<FieldsQuery>,
+    Query(LangQuery { lang }): Query<LangQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let fields = match FieldSelection::parse(fields.fields.as_deref().unwrap_or_default()) {
+        Ok(fields) => fields,
+        Err(e) => return unknown_fields_response(e),
+    };
+
+    let cached = match cached_countries(&state).await {
+        Ok(cached) => cached,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to fetch and simplify countries");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
+
+    let format = ExportFormat::negotiate(format, &headers);
+    let locale = NameLocale::negotiate(lang.as_deref(), &headers);
+    let unfiltered = query == CountryQuery::default() && fields.is_none() && locale.is_english();
+    let etag = if unfiltered && format == ExportFormat::Json {
+        cached.data.etag.clone()
+    } else {
+        http_cache::strong_etag(&[
+            cached.data.etag.as_bytes(),
+            raw_query.unwrap_or_default().as_bytes(),
+            format.as_str().as_bytes(),
+            locale.tag().as_bytes(),
+        ])
+    };
+    let cache_control = http_cache::cache_control(&state.config);
+
+    if http_cache::if_none_match(&headers, &etag) {
+        return http_cache::not_modified(&etag, &cache_control, Some(LIST_VARY));
+    }
+
+    // Unfiltered JSON requests reuse the pre-serialized body
+    let (total, body) = if unfiltered && format == ExportFormat::Json {
+        (
+            cached.data.countries.len(),
+            AxumBody::from(cached.data.body),
+        )
+    } else {
+        let localized;
+        let countries: &[Country] = if locale.is_english() {
+            &cached.data.countries
+        } else {
+            localized = cached
+                .data
+                .countries
+                .iter()
+                .map(|c| locale.localize(c))
+                .collect::<Vec<_>>();
+            &localized
+        };
+        let (total, page) = query.apply(countries, &locale);
+        let page = page.into_iter().cloned().collect();
+        let fields = fields.unwrap_or_default();
+        (total, country_export::stream_body(format, page, fields))
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::VARY, LIST_VARY)
+        .header(header::CONTENT_LANGUAGE, locale.tag())
+        .header("x-data-source", cached.source.as_str())
+        .header("x-total-count", total)
+        .header("x-schema-version", COUNTRY_SCHEMA_VERSION)
+        .body(body)
+        .unwrap()
+        .into_response()
+}
+
+/// Returns a single simplified country by cca2 or cca3 code (case-insensitive).
+///
+/// Reads from the same cache as `api_countries`, so both endpoints always agree. Supports
+/// the same `fields=` selector and name localization.
+pub async fn api_country_by_code(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Query(fields): Query<FieldsQuery>,
+    Query(LangQuery { lang }): Query<LangQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let fields = match FieldSelection::parse(fields.fields.as_deref().unwrap_or_default()) {
+        Ok(fields) => fields,
+        Err(e) => return unknown_fields_response(e),
+    };
+
+    let cached = match cached_countries(&state).await {
+        Ok(cached) => cached,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to fetch and simplify countries");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
+
+    match cached.data.find(&code) {
+        Some(country) => {
+            let selected = fields
+                .as_ref()
+                .map(|f| f.fields().join(","))
+                .unwrap_or_default();
+            let locale = NameLocale::negotiate(lang.as_deref(), &headers);
+            let etag = http_cache::strong_etag(&[
+                cached.data.etag.as_bytes(),
+                country.code.as_bytes(),
+                selected.as_bytes(),
+                locale.tag().as_bytes(),
+            ]);
+            let cache_control = http_cache::cache_control(&state.config);
+
+            if http_cache::if_none_match(&headers, &etag) {
+                return http_cache::not_modified(&etag, &cache_control, Some(LOCALIZED_VARY));
+            }
+
+            (
+                [
+                    (header::ETAG.as_str(), etag),
+                    (header::CACHE_CONTROL.as_str(), cache_control),
+                    (header::VARY.as_str(), LOCALIZED_VARY.to_string()),
+                    (header::CONTENT_LANGUAGE.as_str(), locale.tag().to_string()),
+                    ("x-data-source", cached.source.as_str().to_string()),
+                    ("x-schema-version", COUNTRY_SCHEMA_VERSION.to_string()),
+                ],
+                Json(render_country(&locale.localize(country), fields.as_ref())),
+            )
+                .into_response()
+        }
+        None => country_not_found(&code),
+    }
+}
+
+/// Returns the land neighbours of a country (by cca2 or cca3 code) as simplified records.
+///
+/// Supports the same `fields=` selector and name localization as `api_countries`.
+pub async fn api_country_neighbors(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Query(fields): Query<FieldsQuery>,
+    Query(LangQuery { lang }): Query<LangQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let fields = match FieldSelection::parse(fields.fields.as_deref().unwrap_or_default()) {
+        Ok(fields) => fields,
+        Err(e) => return unknown_fields_response(e),
+    };
+
+    let cached = match cached_countries(&state).await {
+        Ok(cached) => cached,
         Err(e) => {
             tracing::error!(error = %e, "Failed to fetch and simplify countries");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
         }
+    };
+
+    let Some(idx) = cached.data.position(&code) else {
+        return country_not_found(&code);
+    };
+
+    let locale = NameLocale::negotiate(lang.as_deref(), &headers);
+    let etag = http_cache::strong_etag(&[
+        cached.data.etag.as_bytes(),
+        b"neighbors",
+        cached.data.countries[idx].code.as_bytes(),
+        raw_query.unwrap_or_default().as_bytes(),
+        locale.tag().as_bytes(),
+    ]);
+    let cache_control = http_cache::cache_control(&state.config);
+
+    if http_cache::if_none_match(&headers, &etag) {
+        return http_cache::not_modified(&etag, &cache_control, Some(LOCALIZED_VARY));
     }
+
+    let mut neighbors: Vec<Country> = cached
+        .data
+        .graph
+        .neighbors(idx)
+        .iter()
+        .map(|&n| locale.localize(&cached.data.countries[n]))
+        .collect();
+    neighbors.sort_by(|a, b| locale.compare(&a.name, &b.name));
+    let neighbors: Vec<Value> = neighbors
+        .iter()
+        .map(|c| render_country(c, fields.as_ref()))
+        .collect();
+
+    (
+        [
+            (header::ETAG.as_str(), etag),
+            (header::CACHE_CONTROL.as_str(), cache_control),
+            (header::VARY.as_str(), LOCALIZED_VARY.to_string()),
+            (header::CONTENT_LANGUAGE.as_str(), locale.tag().to_string()),
+            ("x-data-source", cached.source.as_str().to_string()),
+            ("x-total-count", neighbors.len().to_string()),
+            ("x-schema-version", COUNTRY_SCHEMA_VERSION.to_string()),
+        ],
+        Json(neighbors),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RouteQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
 }
 
-async fn fetch_and_simplify_countries(state: AppState) -> anyhow::Result<Vec<u8>> {
-    let url = &state.config.country_api_url;
+/// Returns the shortest land route between two countries as a list of simplified records,
+/// found by breadth-first search over the border graph.
+///
+/// Unknown codes and unreachable destinations (islands, other continents) yield `404`.
+pub async fn api_route(
+    State(state): State<AppState>,
+    Query(query): Query<RouteQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (Some(from), Some(to)) = (query.from, query.to) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Both from and to are required"})),
+        )
+            .into_response();
+    };
 
-    let resp = state
-        .reqwest_client
-        .get(url)
-        .send()
-        .await
-        .context("Failed to fetch countries upstream")?;
+    let cached = match cached_countries(&state).await {
+        Ok(cached) => cached,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to fetch and simplify countries");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
 
-    if !resp.status().is_success() {
-        tracing::error!(status = %resp.status(), "Upstream returned non-200");
-        anyhow::bail!("Upstream returned non-200 status: {}", resp.status());
+    let Some(from_idx) = cached.data.position(&from) else {
+        return country_not_found(&from);
+    };
+    let Some(to_idx) = cached.data.position(&to) else {
+        return country_not_found(&to);
+    };
+    let countries = &cached.data.countries;
+
+    let etag = http_cache::strong_etag(&[
+        cached.data.etag.as_bytes(),
+        b"route",
+        countries[from_idx].code.as_bytes(),
+        countries[to_idx].code.as_bytes(),
+    ]);
+    let cache_control = http_cache::cache_control(&state.config);
+
+    if http_cache::if_none_match(&headers, &etag) {
+        return http_cache::not_modified(&etag, &cache_control, None);
     }
 
-    let parsed: Vec<serde_json::Value> =
-        resp.json().await.context("Failed to parse upstream JSON")?;
-
-    let mut simplified: Vec<serde_json::Value> = Vec::with_capacity(parsed.len());
-    for item in parsed.into_iter() {
-        let cca2 = item
-            .get("cca2")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_uppercase();
-        let name = item
-            .get("name")
-            .and_then(|n| n.get("common"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        let region = item
-            .get("region")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        let population = item.get("population").and_then(|v| v.as_u64()).unwrap_or(0);
-        let flag_png = item
-            .get("flags")
-            .and_then(|f| f.get("png"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-
-        simplified.push(serde_json::json!({
-            "code": cca2,
-            "name": if name.is_empty() { cca2.clone() } else { name },
-            "region": region,
-            "population": population,
-            "flag": flag_png,
-        }));
+    let Some(path) = cached.data.graph.shortest_path(from_idx, to_idx) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "No land route",
+                "from": countries[from_idx].code,
+                "to": countries[to_idx].code,
+            })),
+        )
+            .into_response();
+    };
+
+    let path: Vec<Value> = path
+        .iter()
+        .map(|&idx| render_country(&countries[idx], None))
+        .collect();
+    (
+        [
+            (header::ETAG.as_str(), etag),
+            (header::CACHE_CONTROL.as_str(), cache_control),
+            ("x-data-source", cached.source.as_str().to_string()),
+            ("x-schema-version", COUNTRY_SCHEMA_VERSION.to_string()),
+        ],
+        Json(json!({
+            "from": countries[from_idx].code,
+            "to": countries[to_idx].code,
+            "hops": path.len() - 1,
+            "path": path,
+        })),
+    )
+        .into_response()
+}
+
+pub(crate) fn render_country(country: &Country, fields: Option<&FieldSelection>) -> Value {
+    match fields {
+        Some(selection) => selection.project(country),
+        None => FieldSelection::default().project(country),
     }
+}
+
+fn country_not_found(code: &str) -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": "Country not found", "code": code})),
+    )
+        .into_response()
+}
 
-    simplified.sort_by(|a, b| {
-        let na = a.get("name").and_then(|v| v.as_str()).unwrap_or("");
-        let nb = b.get("name").and_then(|v| v.as_str()).unwrap_or("");
-        na.cmp(nb)
-    });
+/// Returns the cached payload, refreshing it in the background once the TTL has expired.
+///
+/// If nothing is cached and upstream fails, falls back to the configured snapshot file.
+pub(crate) async fn cached_countries(state: &AppState) -> anyhow::Result<CachedCountries> {
+    match state.country_cache.lookup() {
+        CacheLookup::Fresh(data) => Ok(data),
+        CacheLookup::Stale(data) => {
+            let inflight = start_refresh(state);
+            if !inflight.coalesced {
+                tokio::spawn(async move {
+                    if let Err(e) = inflight.wait().await {
+                        tracing::warn!(error = %e, "Background country refresh failed; serving stale data");
+                    }
+                });
+            }
+            Ok(data)
+        }
+        CacheLookup::Missing => match start_refresh(state).wait().await {
+            Ok(data) => Ok(CachedCountries {
+                data,
+                source: DataSource::Upstream,
+            }),
+            Err(e) => load_snapshot_fallback(state)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("{e:#}")),
+        },
+    }
+}
+
+/// Loads the on-disk snapshot into the cache after an upstream failure.
+async fn load_snapshot_fallback(state: &AppState) -> anyhow::Result<Option<CachedCountries>> {
+    let Some(path) = state.config.country_snapshot_path.as_deref() else {
+        return Ok(None);
+    };
+
+    let Some(data) = country_snapshot::read(path).await? else {
+        return Ok(None);
+    };
+
+    tracing::warn!(path = %path, "Upstream unavailable; serving country snapshot");
+    state
+        .country_cache
+        .store(data.clone(), DataSource::Snapshot);
+
+    Ok(Some(CachedCountries {
+        data,
+        source: DataSource::Snapshot,
+    }))
+}
 
-    let resp_body =
-        serde_json::to_vec(&simplified).context("Failed to serialize simplified JSON")?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(resp_body)
+    fn country(code: &str, name: &str, region: &str, population: u64) -> Country {
+        Country {
+            code: code.into(),
+            name: name.into(),
+            region: region.into(),
+            population,
+            flag_emoji: crate::country::flag_emoji(code),
+            ..Default::default()
+        }
+    }
+
+    fn sample() -> Vec<Country> {
+        vec![
+            country("DE", "Germany", "Europe", 83),
+            country("FR", "France", "Europe", 67),
+            country("JP", "Japan", "Asia", 125),
+        ]
+    }
+
+    fn codes(page: &[&Country]) -> Vec<String> {
+        page.iter().map(|c| c.code.clone()).collect()
+    }
+
+    #[test]
+    fn filters_by_region_and_query() {
+        let countries = sample();
+        let query = CountryQuery {
+            region: Some("Europe".into()),
+            q: Some(" fr ".into()),
+            ..Default::default()
+        };
+        let (total, page) = query.apply(&countries, &NameLocale::english());
+        assert_eq!(total, 1);
+        assert_eq!(codes(&page), ["FR"]);
+    }
+
+    #[test]
+    fn sorts_and_paginates() {
+        let countries = sample();
+        let query = CountryQuery {
+            region: Some("All".into()),
+            sort: Some(SortKey::Population),
+            order: Some(SortOrder::Desc),
+            limit: Some(2),
+            offset: Some(1),
+            ..Default::default()
+        };
+        let (total, page) = query.apply(&countries, &NameLocale::english());
+        assert_eq!(total, 3);
+        assert_eq!(codes(&page), ["DE", "FR"]);
+    }
 }
@@ -0,0 +1,40 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, header},
+    response::IntoResponse,
+};
+
+use crate::{http_cache, state::AppState};
+
+/// Returns the dataset diffs recorded after recent refreshes, newest first.
+///
+/// The log lives in memory only, so it is empty until the first refresh that changes data.
+/// The ETag follows the newest entry.
+pub async fn api_country_changes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let entries = state.country_changes.entries();
+
+    let newest = entries
+        .first()
+        .map(|entry| format!("{}:{}", entry.version, entry.detected_at))
+        .unwrap_or_default();
+    let etag = http_cache::strong_etag(&[newest.as_bytes(), b"changes"]);
+    let cache_control = http_cache::cache_control(&state.config);
+
+    if http_cache::if_none_match(&headers, &etag) {
+        return http_cache::not_modified(&etag, &cache_control, None);
+    }
+
+    (
+        [
+            (header::ETAG.as_str(), etag),
+            (header::CACHE_CONTROL.as_str(), cache_control),
+            ("x-total-count", entries.len().to_string()),
+        ],
+        Json(entries),
+    )
+        .into_response()
+}
@@ -0,0 +1,99 @@
+use axum::{
+    Json,
+    extract::{Query, RawQuery, State},
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    country::COUNTRY_SCHEMA_VERSION,
+    country_search::MatchKind,
+    handlers::api_countries::{cached_countries, render_country},
+    http_cache,
+    state::AppState,
+};
+
+const DEFAULT_LIMIT: usize = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// One ranked result of `/api/country/search`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResult<'a> {
+    /// The record with the default fields.
+    country: Value,
+    /// The common, official or alternate name (or code) that matched.
+    matched: &'a str,
+    #[serde(rename = "match")]
+    kind: MatchKind,
+    distance: usize,
+}
+
+/// Fuzzy, diacritic-insensitive search over common, official and alternate names.
+///
+/// Results are ranked exact, prefix, word prefix, substring, then typo-tolerant matches by
+/// edit distance. `limit` defaults to 10; the number of matches is sent in `X-Total-Count`.
+pub async fn api_country_search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let Some(q) = query.q.filter(|q| !q.trim().is_empty()) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Missing search query", "param": "q"})),
+        )
+            .into_response();
+    };
+
+    let cached = match cached_countries(&state).await {
+        Ok(cached) => cached,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to fetch and simplify countries");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
+
+    let etag = http_cache::strong_etag(&[
+        cached.data.etag.as_bytes(),
+        b"search",
+        raw_query.unwrap_or_default().as_bytes(),
+    ]);
+    let cache_control = http_cache::cache_control(&state.config);
+
+    if http_cache::if_none_match(&headers, &etag) {
+        return http_cache::not_modified(&etag, &cache_control, None);
+    }
+
+    let hits = cached.data.search.search(&q);
+    let results: Vec<SearchResult> = hits
+        .iter()
+        .take(query.limit.unwrap_or(DEFAULT_LIMIT))
+        .map(|hit| SearchResult {
+            country: render_country(&cached.data.countries[hit.index], None),
+            matched: &hit.matched,
+            kind: hit.kind,
+            distance: hit.distance,
+        })
+        .collect();
+
+    (
+        [
+            (header::ETAG.as_str(), etag),
+            (header::CACHE_CONTROL.as_str(), cache_control),
+            ("x-data-source", cached.source.as_str().to_string()),
+            ("x-total-count", hits.len().to_string()),
+            ("x-schema-version", COUNTRY_SCHEMA_VERSION.to_string()),
+        ],
+        Json(results),
+    )
+        .into_response()
+}
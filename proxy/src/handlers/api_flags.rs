@@ -0,0 +1,123 @@
+use axum::{
+    Json,
+    body::{Body as AxumBody, Bytes},
+    extract::{Path, State},
+    http::{HeaderMap, Response, StatusCode, header},
+    response::IntoResponse,
+};
+use metrics::counter;
+use serde_json::json;
+
+use crate::{
+    flag_cache::{self, FlagFormat},
+    handlers::api_countries::cached_countries,
+    http_cache,
+    state::AppState,
+};
+
+/// Flag images never change for a given URL, so they may be cached for a year.
+const FLAG_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Blocks scripts and external loads inside SVG flags opened directly.
+const SVG_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'; sandbox";
+
+/// Serves a flag image from the on-disk cache, fetching it from the flag CDN on first use.
+///
+/// Flags already on disk are served without consulting the country data, so they keep
+/// working while it cannot be loaded. On a miss, only codes present in the country data are
+/// fetched, so arbitrary names cannot fill the cache directory.
+pub async fn api_flag(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let Some((code, format)) = flag_cache::parse_file_name(&file) else {
+        return flag_not_found(&file);
+    };
+
+    let bytes = match state.flag_cache.cached(&code, format).await {
+        Ok(Some(bytes)) => {
+            counter!("flag_cache_requests_total", "result" => "hit").increment(1);
+            bytes
+        }
+        Ok(None) => match fetch_flag(&state, &code, format).await {
+            Ok(bytes) => bytes,
+            Err(response) => return response,
+        },
+        Err(e) => {
+            tracing::warn!(code = %code, error = %e, "Failed to read cached flag");
+            counter!("flag_cache_requests_total", "result" => "error").increment(1);
+            return flag_unavailable(&code);
+        }
+    };
+
+    let etag = http_cache::strong_etag(&[&bytes]);
+    if http_cache::if_none_match(&headers, &etag) {
+        return http_cache::not_modified(&etag, FLAG_CACHE_CONTROL, None);
+    }
+
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(header::CACHE_CONTROL, FLAG_CACHE_CONTROL)
+        .header(header::ETAG, etag)
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+    if format == FlagFormat::Svg {
+        builder = builder.header(header::CONTENT_SECURITY_POLICY, SVG_CONTENT_SECURITY_POLICY);
+    }
+
+    builder.body(AxumBody::from(bytes)).unwrap().into_response()
+}
+
+/// Validates `code` against the country data and fetches the flag from upstream.
+async fn fetch_flag(
+    state: &AppState,
+    code: &str,
+    format: FlagFormat,
+) -> Result<Bytes, axum::response::Response> {
+    let cached = match cached_countries(state).await {
+        Ok(cached) => cached,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to fetch and simplify countries");
+            return Err(
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+            );
+        }
+    };
+    if cached.data.find(code).is_none() {
+        return Err(flag_not_found(&format!("{code}.{}", format.extension())));
+    }
+
+    let (outcome, coalesced) = state
+        .flag_cache
+        .fetch(&state.reqwest_client, code, format)
+        .await;
+    match outcome {
+        Ok(bytes) => {
+            let result = if coalesced { "coalesced" } else { "miss" };
+            counter!("flag_cache_requests_total", "result" => result).increment(1);
+            Ok(bytes)
+        }
+        Err(e) => {
+            tracing::warn!(code = %code, error = %e, "Failed to load flag");
+            counter!("flag_cache_requests_total", "result" => "error").increment(1);
+            Err(flag_unavailable(code))
+        }
+    }
+}
+
+fn flag_unavailable(code: &str) -> axum::response::Response {
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(json!({ "error": "Flag unavailable", "code": code.to_ascii_uppercase() })),
+    )
+        .into_response()
+}
+
+fn flag_not_found(file: &str) -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Flag not found", "file": file })),
+    )
+        .into_response()
+}
@@ -0,0 +1,165 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    country::Country, handlers::api_countries::cached_countries, http_cache, state::AppState,
+};
+
+/// Aggregate figures for one region (or subregion).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionSummary {
+    pub name: String,
+    pub country_count: usize,
+    pub population: u64,
+    pub largest_country: LargestCountry,
+    /// Per-subregion breakdown; empty (and omitted) for subregion entries themselves.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subregions: Vec<RegionSummary>,
+}
+
+/// The most populous country of a region.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LargestCountry {
+    pub code: String,
+    pub name: String,
+    pub population: u64,
+}
+
+/// Groups countries by region and subregion, both sorted by name.
+///
+/// Countries without a subregion count towards their region only.
+pub fn summarize(countries: &[Country]) -> Vec<RegionSummary> {
+    let mut regions: BTreeMap<&str, Vec<&Country>> = BTreeMap::new();
+    for country in countries {
+        regions.entry(&country.region).or_default().push(country);
+    }
+
+    regions
+        .into_iter()
+        .map(|(region, members)| {
+            let mut subregions: BTreeMap<&str, Vec<&Country>> = BTreeMap::new();
+            for country in &members {
+                if let Some(subregion) = country.subregion.as_deref() {
+                    subregions.entry(subregion).or_default().push(country);
+                }
+            }
+
+            RegionSummary {
+                subregions: subregions
+                    .into_iter()
+                    .map(|(name, members)| summarize_group(name, &members))
+                    .collect(),
+                ..summarize_group(region, &members)
+            }
+        })
+        .collect()
+}
+
+fn summarize_group(name: &str, members: &[&Country]) -> RegionSummary {
+    // Ties go to the alphabetically first name, since input is sorted by name
+    let largest = members
+        .iter()
+        .copied()
+        .reduce(|best, c| {
+            if c.population > best.population {
+                c
+            } else {
+                best
+            }
+        })
+        .expect("groups are never empty");
+
+    RegionSummary {
+        name: name.to_string(),
+        country_count: members.len(),
+        population: members.iter().map(|c| c.population).sum(),
+        largest_country: LargestCountry {
+            code: largest.code.clone(),
+            name: largest.name.clone(),
+            population: largest.population,
+        },
+        subregions: Vec::new(),
+    }
+}
+
+/// Returns per-region country counts, total population and largest country.
+///
+/// Computed from the same cache as `api_countries`, with an ETag derived from the data
+/// version so unchanged data yields `304 Not Modified`.
+pub async fn api_regions(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    let cached = match cached_countries(&state).await {
+        Ok(cached) => cached,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to fetch and simplify countries");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
+
+    let etag = http_cache::strong_etag(&[cached.data.etag.as_bytes(), b"regions"]);
+    let cache_control = http_cache::cache_control(&state.config);
+
+    if http_cache::if_none_match(&headers, &etag) {
+        return http_cache::not_modified(&etag, &cache_control, None);
+    }
+
+    (
+        [
+            (header::ETAG.as_str(), etag),
+            (header::CACHE_CONTROL.as_str(), cache_control),
+            ("x-data-source", cached.source.as_str().to_string()),
+        ],
+        Json(summarize(&cached.data.countries)),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn country(code: &str, region: &str, subregion: Option<&str>, population: u64) -> Country {
+        Country {
+            code: code.into(),
+            name: format!("Country {code}"),
+            region: region.into(),
+            subregion: subregion.map(Into::into),
+            population,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aggregates_regions_and_subregions() {
+        let countries = vec![
+            country("DE", "Europe", Some("Western Europe"), 83),
+            country("ES", "Europe", Some("Southern Europe"), 47),
+            country("FR", "Europe", Some("Western Europe"), 67),
+            country("AX", "Europe", None, 1),
+            country("JP", "Asia", Some("Eastern Asia"), 125),
+        ];
+
+        let regions = summarize(&countries);
+        let names: Vec<_> = regions.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["Asia", "Europe"]);
+
+        let europe = &regions[1];
+        assert_eq!(europe.country_count, 4);
+        assert_eq!(europe.population, 198);
+        assert_eq!(europe.largest_country.code, "DE");
+
+        let western = &europe.subregions[1];
+        assert_eq!(western.name, "Western Europe");
+        assert_eq!(western.country_count, 2);
+        assert_eq!(western.population, 150);
+        assert!(western.subregions.is_empty());
+        assert_eq!(europe.subregions.len(), 2);
+    }
+}
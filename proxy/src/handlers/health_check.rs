@@ -1,15 +1,27 @@
-use axum::{Json, response::IntoResponse};
+use axum::{Json, extract::State, response::IntoResponse};
 use serde_json::json;
 
+use crate::state::AppState;
+
 /// Health check endpoint handler
 ///
 /// Returns a simple JSON response indicating the service is healthy.
 /// Useful for container orchestration, load balancers, and monitoring systems.
+/// Also reports the state of the country API circuit breaker and the outcome of the
+/// most recent country data refreshes (Unix timestamps in seconds).
 ///
 /// # Example Response
 /// ```json
-/// {"isHealthy": true}
+/// {
+///   "isHealthy": true,
+///   "countryBreaker": "closed",
+///   "countryRefresh": {"lastSuccessAt": 1760000000, "lastErrorAt": null, "lastError": null}
+/// }
 /// ```
-pub async fn health_check() -> impl IntoResponse {
-    Json(json!({"isHealthy": true}))
+pub async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
+    Json(json!({
+        "isHealthy": true,
+        "countryBreaker": state.country_breaker.state().as_str(),
+        "countryRefresh": state.country_cache.refresh_status(),
+    }))
 }
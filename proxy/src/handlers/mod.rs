@@ -1,5 +1,9 @@
 pub mod api_countries;
+pub mod api_country_changes;
+pub mod api_country_search;
 pub mod api_events;
+pub mod api_flags;
+pub mod api_regions;
 pub mod api_themes;
 pub mod health_check;
 pub mod metrics;
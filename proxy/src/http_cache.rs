@@ -0,0 +1,111 @@
+//! HTTP validator and cache header helpers shared by the JSON API handlers.
+
+use axum::{
+    body::Body as AxumBody,
+    http::{HeaderMap, Response, StatusCode, header},
+    response::IntoResponse,
+};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+
+/// Computes a strong ETag (quoted SHA-256 hex digest) over the given parts.
+///
+/// Each part is prefixed with its length, so moving bytes between parts changes the tag.
+pub fn strong_etag(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    format!("\"{:x}\"", hasher.finalize())
+}
+
+/// Returns `true` if the request's `If-None-Match` header matches `etag`.
+///
+/// Uses the weak comparison required for `If-None-Match`, so `W/` prefixes are ignored.
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// Builds the `Cache-Control` value for JSON API responses from configuration.
+pub fn cache_control(config: &Config) -> String {
+    if config.api_stale_while_revalidate_secs == 0 {
+        config.api_cache_control.clone()
+    } else {
+        format!(
+            "{}, stale-while-revalidate={}",
+            config.api_cache_control, config.api_stale_while_revalidate_secs
+        )
+    }
+}
+
+/// Builds an empty `304 Not Modified` response carrying the validators.
+///
+/// `vary` must match the `Vary` header of the `200` response, so shared caches apply the
+/// revalidation to the same variant only.
+pub fn not_modified(
+    etag: &str,
+    cache_control: &str,
+    vary: Option<&str>,
+) -> axum::response::Response {
+    let mut response = Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control);
+    if let Some(vary) = vary {
+        response = response.header(header::VARY, vary);
+    }
+    response.body(AxumBody::empty()).unwrap().into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn matches_if_none_match_lists_and_weak_tags() {
+        let etag = strong_etag(&[b"body"]);
+
+        assert!(if_none_match(
+            &headers(&format!("\"other\", W/{etag}")),
+            &etag
+        ));
+        assert!(if_none_match(&headers("*"), &etag));
+        assert!(!if_none_match(&headers("\"other\""), &etag));
+        assert!(!if_none_match(&HeaderMap::new(), &etag));
+    }
+
+    #[test]
+    fn strong_etag_is_quoted_and_content_dependent() {
+        let etag = strong_etag(&[b"abc"]);
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert_ne!(etag, strong_etag(&[b"abd"]));
+        assert_ne!(strong_etag(&[b"ab", b"c"]), strong_etag(&[b"a", b"bc"]));
+    }
+
+    #[test]
+    fn not_modified_repeats_vary() {
+        let response = not_modified("\"tag\"", "no-cache", Some("Accept-Language"));
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], "\"tag\"");
+        assert_eq!(response.headers()[header::VARY], "Accept-Language");
+
+        let response = not_modified("\"tag\"", "no-cache", None);
+        assert!(!response.headers().contains_key(header::VARY));
+    }
+}
@@ -0,0 +1,231 @@
+//! Display-language negotiation for country names.
+//!
+//! restcountries keys its `translations` by ISO 639-3 code, while clients send BCP 47 tags in
+//! `Accept-Language`, so negotiation maps primary language subtags onto those keys.
+
+use axum::http::{HeaderMap, header};
+use icu_collator::{Collator, CollatorBorrowed, options::CollatorOptions};
+use icu_locale_core::Locale;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use crate::country::Country;
+
+/// Languages restcountries translates names into: (BCP 47 primary subtag, ISO 639-3 key).
+const TRANSLATIONS: &[(&str, &str)] = &[
+    ("ar", "ara"),
+    ("br", "bre"),
+    ("cs", "ces"),
+    ("cy", "cym"),
+    ("de", "deu"),
+    ("es", "spa"),
+    ("et", "est"),
+    ("fa", "per"),
+    ("fi", "fin"),
+    ("fr", "fra"),
+    ("hr", "hrv"),
+    ("hu", "hun"),
+    ("it", "ita"),
+    ("ja", "jpn"),
+    ("ko", "kor"),
+    ("nl", "nld"),
+    ("pl", "pol"),
+    ("pt", "por"),
+    ("ru", "rus"),
+    ("sk", "slk"),
+    ("sr", "srp"),
+    ("sv", "swe"),
+    ("tr", "tur"),
+    ("ur", "urd"),
+    ("zh", "zho"),
+];
+
+/// Most collators kept by [`collator_for`]; tags are client input, so the cache is bounded.
+const MAX_CACHED_COLLATORS: usize = 64;
+
+/// Collators by canonical locale. Building one takes far longer than a typical sort.
+static COLLATORS: LazyLock<Mutex<HashMap<String, Arc<CollatorBorrowed<'static>>>>> =
+    LazyLock::new(Mutex::default);
+
+/// Returns the cached collator for `tag`, building it on first use.
+fn collator_for(tag: &str) -> Arc<CollatorBorrowed<'static>> {
+    let locale = Locale::try_from_str(tag).unwrap_or_else(|_| "en".parse().unwrap());
+    let key = locale.to_string();
+    if let Some(collator) = COLLATORS.lock().expect("collator cache poisoned").get(&key) {
+        return Arc::clone(collator);
+    }
+
+    let collator = Arc::new(
+        Collator::try_new((&locale).into(), CollatorOptions::default())
+            .or_else(|_| Collator::try_new(Default::default(), CollatorOptions::default()))
+            .expect("root collation data is compiled in"),
+    );
+    let mut cache = COLLATORS.lock().expect("collator cache poisoned");
+    if cache.len() < MAX_CACHED_COLLATORS {
+        cache.insert(key, Arc::clone(&collator));
+    }
+    collator
+}
+
+/// The language country names are displayed and sorted in.
+pub struct NameLocale {
+    /// BCP 47 tag sent back in `Content-Language`.
+    tag: String,
+    /// restcountries translation key; `None` for English.
+    key: Option<&'static str>,
+    collator: Arc<CollatorBorrowed<'static>>,
+}
+
+impl NameLocale {
+    pub fn english() -> Self {
+        Self::new("en".to_string(), None)
+    }
+
+    fn new(tag: String, key: Option<&'static str>) -> Self {
+        let collator = collator_for(&tag);
+        Self { tag, key, collator }
+    }
+
+    /// Picks the display language: an explicit `lang=` wins, then the highest-q supported
+    /// `Accept-Language` range. Anything unsupported falls back to English.
+    pub fn negotiate(lang: Option<&str>, headers: &HeaderMap) -> Self {
+        let mut ranges: Vec<(String, f32)> = match lang {
+            Some(lang) => vec![(lang.trim().to_string(), 1.0)],
+            None => headers
+                .get_all(header::ACCEPT_LANGUAGE)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .filter_map(|range| {
+                    let mut parts = range.split(';').map(str::trim);
+                    let tag = parts.next().filter(|t| !t.is_empty())?;
+                    let q = parts
+                        .filter_map(|p| p.strip_prefix("q="))
+                        .find_map(|q| q.parse::<f32>().ok())
+                        .unwrap_or(1.0);
+                    (q > 0.0).then(|| (tag.to_string(), q))
+                })
+                .collect(),
+        };
+        // Stable, so equal weights keep header order
+        ranges.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+
+        for (tag, _) in ranges {
+            let primary = tag
+                .split(['-', '_'])
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            if primary == "en" || primary == "eng" || primary == "*" {
+                return Self::english();
+            }
+            if let Some((subtag, key)) = TRANSLATIONS
+                .iter()
+                .find(|(subtag, key)| *subtag == primary || *key == primary)
+            {
+                // Keep regional variants for collation, but only for two-letter tags
+                let tag = if primary.len() == 2 {
+                    tag.replace('_', "-")
+                } else {
+                    subtag.to_string()
+                };
+                return Self::new(tag, Some(key));
+            }
+        }
+
+        Self::english()
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn is_english(&self) -> bool {
+        self.key.is_none()
+    }
+
+    /// The country's name in this language, falling back to the English common name.
+    pub fn name<'a>(&self, country: &'a Country) -> &'a str {
+        self.key
+            .and_then(|key| country.translations.get(key))
+            .map(String::as_str)
+            .unwrap_or(&country.name)
+    }
+
+    /// Returns a copy of `country` with `name` translated into this language.
+    pub fn localize(&self, country: &Country) -> Country {
+        Country {
+            name: self.name(country).to_string(),
+            ..country.clone()
+        }
+    }
+
+    /// Compares two strings with this language's collation rules.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.collator.compare(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn accept_language(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT_LANGUAGE,
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn negotiates_by_quality_and_falls_back_to_english() {
+        let locale = NameLocale::negotiate(None, &accept_language("xx, de;q=0.5, fr-CH;q=0.8"));
+        assert_eq!(locale.tag(), "fr-CH");
+        assert_eq!(locale.key, Some("fra"));
+
+        let locale = NameLocale::negotiate(Some("jpn"), &accept_language("de"));
+        assert_eq!((locale.tag(), locale.key), ("ja", Some("jpn")));
+
+        assert!(NameLocale::negotiate(None, &accept_language("en-GB, de;q=0.9")).is_english());
+        assert!(NameLocale::negotiate(None, &accept_language("xx, de;q=0")).is_english());
+        assert!(NameLocale::negotiate(None, &HeaderMap::new()).is_english());
+    }
+
+    #[test]
+    fn translates_with_english_fallback() {
+        let france = Country {
+            name: "France".into(),
+            translations: [("deu".to_string(), "Frankreich".to_string())].into(),
+            ..Default::default()
+        };
+
+        let german = NameLocale::negotiate(Some("de"), &HeaderMap::new());
+        assert_eq!(german.name(&france), "Frankreich");
+        let spanish = NameLocale::negotiate(Some("es"), &HeaderMap::new());
+        assert_eq!(spanish.name(&france), "France");
+    }
+
+    #[test]
+    fn collates_per_language() {
+        // Swedish sorts Å after Z; English treats it as a variant of A
+        let swedish = NameLocale::negotiate(Some("sv"), &HeaderMap::new());
+        assert_eq!(swedish.compare("Åland", "Zambia"), Ordering::Greater);
+        let english = NameLocale::english();
+        assert_eq!(english.compare("Åland", "Zambia"), Ordering::Less);
+        assert_eq!(english.compare("Côte d'Ivoire", "Cuba"), Ordering::Less);
+    }
+
+    #[test]
+    fn reuses_collators_per_locale() {
+        let a = NameLocale::negotiate(Some("fr-ch"), &HeaderMap::new());
+        let b = NameLocale::negotiate(None, &accept_language("fr-CH"));
+        assert!(Arc::ptr_eq(&a.collator, &b.collator));
+        assert!(!Arc::ptr_eq(&a.collator, &NameLocale::english().collator));
+    }
+}
@@ -8,9 +8,29 @@
 //! - Pre-compressed static asset serving (br/gz)
 //! - Strong caching headers for versioned assets
 
+mod circuit_breaker;
+mod color;
 mod config;
+mod country;
+mod country_cache;
+mod country_changes;
+mod country_export;
+mod country_graph;
+mod country_refresher;
+mod country_search;
+mod country_snapshot;
+mod country_source;
+mod css;
+mod flag_cache;
 mod handlers;
+mod http_cache;
+mod locale;
+mod retry;
+mod single_flight;
 mod state;
+mod theme_audit;
+mod theme_watcher;
+mod themes;
 
 use anyhow::Context;
 use axum::{
@@ -18,25 +38,40 @@ use axum::{
     routing::{get, post},
 };
 use hyper_util::client::legacy::{Client, connect::HttpConnector};
-use std::{env, net::SocketAddr, sync::Arc};
-use tokio::signal;
+use std::{env, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{signal, sync::watch};
 use tower::ServiceBuilder;
 use tower_http::{compression::CompressionLayer, services::ServeFile, trace::TraceLayer};
 
-use metrics::{describe_counter, describe_histogram};
+use metrics::{describe_counter, describe_gauge, describe_histogram};
 use metrics_exporter_prometheus::PrometheusBuilder;
 use tower_governor::{
-    governor::GovernorConfigBuilder, key_extractor::GlobalKeyExtractor, GovernorLayer,
+    GovernorLayer, governor::GovernorConfigBuilder, key_extractor::GlobalKeyExtractor,
 };
 use tracing_subscriber::{EnvFilter, Registry, layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::{
+    circuit_breaker::CircuitBreaker,
     config::Config,
+    country_cache::{CountryCache, DataSource},
+    country_changes::ChangeLog,
+    flag_cache::FlagCache,
     handlers::{
-        api_countries::api_countries, api_events::api_events, api_themes::api_themes, health_check::health_check,
-        metrics::metrics_handler, proxy_fallback::proxy_fallback, serve_asset::serve_asset,
+        api_countries::{api_countries, api_country_by_code, api_country_neighbors, api_route},
+        api_country_changes::api_country_changes,
+        api_country_search::api_country_search,
+        api_events::api_events,
+        api_flags::api_flag,
+        api_regions::api_regions,
+        api_themes::{api_themes, api_themes_audit},
+        health_check::health_check,
+        metrics::metrics_handler,
+        proxy_fallback::proxy_fallback,
+        serve_asset::serve_asset,
     },
     state::AppState,
+    theme_audit::NonCompliantThemes,
+    themes::ThemeStore,
 };
 
 /// Initializes tracing with optional JSON formatting.
@@ -119,6 +154,36 @@ async fn main() -> anyhow::Result<()> {
         "Total number of frontend events received"
     );
 
+    describe_counter!(
+        "country_fetch_coalesced_total",
+        "Country requests and refreshes that joined an in-flight upstream fetch"
+    );
+
+    describe_counter!(
+        "country_fetch_retries_total",
+        "Retry attempts made for the upstream country fetch"
+    );
+
+    describe_gauge!(
+        "country_breaker_state",
+        "Country API circuit breaker state (0 = closed, 1 = half-open, 2 = open)"
+    );
+
+    describe_gauge!(
+        "country_last_refresh_success_timestamp_seconds",
+        "Unix time of the last successful country data refresh"
+    );
+
+    describe_gauge!(
+        "country_last_refresh_error_timestamp_seconds",
+        "Unix time of the last failed country data refresh"
+    );
+
+    describe_counter!(
+        "flag_cache_requests_total",
+        "Flag image requests by cache result (hit, miss, coalesced, error)"
+    );
+
     tracing::info!("Initializing proxy server");
 
     let config = Arc::new(Config::load("proxy/proxy.ron".as_ref())?);
@@ -130,7 +195,56 @@ async fn main() -> anyhow::Result<()> {
     let rate_limit_burst_size = config.rate_limit_burst_size;
 
     let client = Client::builder(hyper_util::rt::TokioExecutor::new()).build(HttpConnector::new());
-    let reqwest_client = reqwest::Client::new();
+    let reqwest_client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_millis(config.country_connect_timeout_ms))
+        .timeout(Duration::from_millis(config.country_request_timeout_ms))
+        .build()
+        .context("Failed to build HTTP client")?;
+    let country_cache = Arc::new(CountryCache::new(Duration::from_secs(
+        config.country_cache_ttl_secs,
+    )));
+
+    let country_source = config.country_source();
+
+    let country_breaker = Arc::new(CircuitBreaker::new(
+        config.country_breaker_failure_threshold,
+        Duration::from_secs(config.country_breaker_cooldown_secs),
+    ));
+
+    let country_changes = Arc::new(ChangeLog::new(config.country_change_log_size));
+
+    let flag_cache = Arc::new(FlagCache::new(
+        &config.flag_cache_dir,
+        &config.flag_png_url,
+        &config.flag_svg_url,
+    ));
+
+    // Parse themes once up front; requests serve the cached list
+    let themes = Arc::new(ThemeStore::new(
+        &config.themes_css_path,
+        &config.themes_required_colors,
+        config.themes_contrast_mode,
+    ));
+    match themes.reload() {
+        Ok(_) => {}
+        // `themes_contrast_mode: Enforce` turns a contrast failure into a startup failure
+        Err(e) if e.is::<NonCompliantThemes>() => return Err(e),
+        Err(e) => tracing::error!(error = %e, "Failed to load themes"),
+    }
+
+    // Seed the cache from the last good snapshot so we can serve without upstream
+    if let Some(path) = config.country_snapshot_path.as_deref() {
+        match country_snapshot::read(path).await {
+            Ok(Some(data)) => {
+                tracing::info!(path = %path, countries = data.countries.len(), "Loaded country snapshot");
+                country_cache.store(data, DataSource::Snapshot);
+            }
+            Ok(None) => tracing::info!(path = %path, "No country snapshot found"),
+            Err(e) => {
+                tracing::warn!(path = %path, error = %e, "Ignoring unreadable country snapshot")
+            }
+        }
+    }
 
     let state = AppState {
         client,
@@ -141,8 +255,32 @@ async fn main() -> anyhow::Result<()> {
         asset_root: Arc::new(asset_dir.clone()),
 
         config,
+
+        country_cache,
+
+        country_breaker,
+
+        country_source: Arc::new(country_source),
+
+        country_changes,
+
+        flag_cache,
+
+        themes,
     };
 
+    // Background refresher: pre-warms country data and keeps it fresh until shutdown
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let refresher = tokio::spawn(country_refresher::run(state.clone(), shutdown_rx.clone()));
+
+    // Hot-reload themes while designers edit the CSS
+    let theme_watcher = state.config.themes_hot_reload.then(|| {
+        tokio::spawn(theme_watcher::run(
+            Arc::clone(&state.themes),
+            shutdown_rx.clone(),
+        ))
+    });
+
     let app = Router::new()
         // Health check endpoint
         .route("/isHealthy", get(health_check))
@@ -158,7 +296,15 @@ async fn main() -> anyhow::Result<()> {
         .route("/api/events", post(api_events))
         // API endpoint for country data (served by Rust proxy)
         .route("/api/country", get(api_countries))
+        .route("/api/country/search", get(api_country_search))
+        .route("/api/country/changes", get(api_country_changes))
+        .route("/api/country/{code}", get(api_country_by_code))
+        .route("/api/country/{code}/neighbors", get(api_country_neighbors))
+        .route("/api/route", get(api_route))
+        .route("/api/regions", get(api_regions))
+        .route("/api/flags/{file}", get(api_flag))
         .route("/api/themes", get(api_themes))
+        .route("/api/themes/audit", get(api_themes_audit))
         .route("/assets/{*path}", get(serve_asset))
         .route_service(
             "/favicon.ico",
@@ -210,10 +356,23 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!(listen_addr = %addr, "Listening for requests");
 
     axum::serve(listener, app.into_make_service())
-        .with_graceful_shutdown(shutdown_signal())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            let _ = shutdown_tx.send(true);
+        })
         .await
         .context("Server error")?;
 
+    if let Err(e) = refresher.await {
+        tracing::warn!(error = %e, "Country refresher task failed");
+    }
+
+    if let Some(theme_watcher) = theme_watcher
+        && let Err(e) = theme_watcher.await
+    {
+        tracing::warn!(error = %e, "Theme watcher task failed");
+    }
+
     tracing::info!("Server stopped gracefully");
 
     Ok(())
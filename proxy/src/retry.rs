@@ -0,0 +1,44 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Bounded exponential backoff with full jitter.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Upper bound of the delay before retry number `retry` (1-based): `base * 2^(retry-1)`,
+    /// capped at `max_delay`.
+    pub fn delay_cap(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Random delay in `[0, delay_cap(retry)]`, so concurrent clients do not retry in lockstep.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let cap = self.delay_cap(retry);
+        Duration::from_millis(rand::rng().random_range(0..=cap.as_millis() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_until_capped() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+        };
+        assert_eq!(policy.delay_cap(1), Duration::from_millis(100));
+        assert_eq!(policy.delay_cap(2), Duration::from_millis(200));
+        assert_eq!(policy.delay_cap(3), Duration::from_millis(350));
+        assert!(policy.backoff(2) <= Duration::from_millis(200));
+    }
+}
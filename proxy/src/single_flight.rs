@@ -0,0 +1,143 @@
+//! Single-flight execution: concurrent callers asking for the same key share one run.
+//!
+//! Used by `CountryCache` for upstream country fetches and by `FlagCache` for flag downloads.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::watch;
+
+/// Outcome of a shared run, cloned to every caller waiting on it.
+pub type SharedOutcome<T> = Result<T, Arc<anyhow::Error>>;
+
+type Flights<K, T> = Mutex<HashMap<K, watch::Receiver<Option<SharedOutcome<T>>>>>;
+
+/// Runs that are in flight, keyed by what they produce.
+pub struct SingleFlight<K, T> {
+    inflight: Arc<Flights<K, T>>,
+}
+
+/// Handle to a run that is in flight (or has just finished).
+pub struct Flight<T> {
+    rx: watch::Receiver<Option<SharedOutcome<T>>>,
+    /// `true` when this caller joined a run started by someone else.
+    pub coalesced: bool,
+}
+
+impl<T: Clone> Flight<T> {
+    /// Waits for the run to finish and returns its shared outcome.
+    pub async fn wait(mut self) -> SharedOutcome<T> {
+        match self.rx.wait_for(Option::is_some).await {
+            Ok(outcome) => outcome.clone().expect("checked by wait_for"),
+            Err(_) => Err(Arc::new(anyhow::anyhow!(
+                "Single-flight task ended without a result"
+            ))),
+        }
+    }
+}
+
+impl<K, T> SingleFlight<K, T>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+    T: Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            inflight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Starts the future made by `work` for `key`, or joins the run already in flight for it.
+    ///
+    /// `work` is only called when a new run starts. The future runs on its own task so that a
+    /// cancelled caller cannot abandon the others, and the key is free again before its
+    /// outcome is published.
+    pub fn run<F, Fut>(&self, key: K, work: F) -> Flight<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        let mut inflight = self.inflight.lock().expect("single-flight lock poisoned");
+        // A closed channel means the previous task died; start a new one.
+        if let Some(rx) = inflight.get(&key).filter(|rx| rx.has_changed().is_ok()) {
+            return Flight {
+                rx: rx.clone(),
+                coalesced: true,
+            };
+        }
+
+        let (tx, rx) = watch::channel(None);
+        inflight.insert(key.clone(), rx.clone());
+        drop(inflight);
+
+        let fut = work();
+        let flights = Arc::clone(&self.inflight);
+        tokio::spawn(async move {
+            let outcome = fut.await.map_err(Arc::new);
+            flights
+                .lock()
+                .expect("single-flight lock poisoned")
+                .remove(&key);
+            let _ = tx.send(Some(outcome));
+        });
+
+        Flight {
+            rx,
+            coalesced: false,
+        }
+    }
+}
+
+impl<K, T> Default for SingleFlight<K, T>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+    T: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn shares_runs_per_key() {
+        let flights = SingleFlight::<&str, u32>::new();
+        let slow = |value| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(value)
+        };
+
+        let first = flights.run("a", || slow(1));
+        let joined = flights.run("a", || slow(2));
+        let other = flights.run("b", || slow(3));
+        assert!(!first.coalesced && joined.coalesced && !other.coalesced);
+
+        assert_eq!(first.wait().await.unwrap(), 1);
+        assert_eq!(joined.wait().await.unwrap(), 1);
+        assert_eq!(other.wait().await.unwrap(), 3);
+
+        // A finished run frees its key
+        let next = flights.run("a", || slow(4));
+        assert!(!next.coalesced);
+        assert_eq!(next.wait().await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn replaces_runs_whose_task_died() {
+        let flights = SingleFlight::<(), u32>::new();
+
+        let died = flights.run((), || async { panic!("task died") });
+        assert!(died.wait().await.is_err());
+
+        let next = flights.run((), || async { Ok(1) });
+        assert!(!next.coalesced);
+        assert_eq!(next.wait().await.unwrap(), 1);
+    }
+}
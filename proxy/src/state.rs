@@ -2,7 +2,11 @@ use axum::body::Body as AxumBody;
 use hyper_util::client::legacy::{Client, connect::HttpConnector};
 use std::sync::Arc;
 
-use crate::config::Config;
+use crate::{
+    circuit_breaker::CircuitBreaker, config::Config, country_cache::CountryCache,
+    country_changes::ChangeLog, country_source::CountrySource, flag_cache::FlagCache,
+    themes::ThemeStore,
+};
 
 pub type HttpClient = Client<HttpConnector, AxumBody>;
 
@@ -13,4 +17,10 @@ pub struct AppState {
     pub upstream_base: Arc<String>,
     pub asset_root: Arc<String>,
     pub config: Arc<Config>,
+    pub country_cache: Arc<CountryCache>,
+    pub country_breaker: Arc<CircuitBreaker>,
+    pub country_source: Arc<CountrySource>,
+    pub country_changes: Arc<ChangeLog>,
+    pub flag_cache: Arc<FlagCache>,
+    pub themes: Arc<ThemeStore>,
 }
@@ -0,0 +1,189 @@
+//! WCAG 2.1 contrast checks for the text/background color pairs each theme defines.
+//!
+//! A pair is `base-content` on every `base-*` shade, plus `X-content` on `X` for every other
+//! color that has a content color. Ratios are checked against the normal-text thresholds.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{color::Color, themes::Theme};
+
+/// Minimum contrast for normal text at level AA.
+pub const AA_RATIO: f64 = 4.5;
+/// Minimum contrast for normal text at level AAA.
+pub const AAA_RATIO: f64 = 7.0;
+
+/// What loading a theme below AA does, set with `themes_contrast_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ContrastMode {
+    /// Only report it through `/api/themes/audit`.
+    Off,
+    /// Also log a warning for each failing pair.
+    Warn,
+    /// Refuse the themes: startup fails, and a hot reload keeps the previous list.
+    Enforce,
+}
+
+/// One text color measured against its background.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContrastCheck {
+    pub foreground: String,
+    pub background: String,
+    pub foreground_value: String,
+    pub background_value: String,
+    /// Rounded to two decimals; the flags use the exact value.
+    pub ratio: f64,
+    pub aa: bool,
+    pub aaa: bool,
+}
+
+impl fmt::Display for ContrastCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on {} is {}:1",
+            self.foreground, self.background, self.ratio
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeAudit {
+    pub name: String,
+    /// Every pair meets AA (or AAA); `true` when the theme has no pairs.
+    pub aa: bool,
+    pub aaa: bool,
+    pub checks: Vec<ContrastCheck>,
+    /// Pairs that could not be measured because a color did not parse.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+impl ThemeAudit {
+    pub fn new(theme: &Theme) -> Self {
+        let mut checks = Vec::new();
+        let mut skipped = Vec::new();
+
+        for (foreground, background) in pairs(theme) {
+            let fg_value = theme.tokens.color(foreground).unwrap_or_default();
+            let bg_value = theme.tokens.color(background).unwrap_or_default();
+            let (Ok(fg), Ok(bg)) = (Color::parse(fg_value), Color::parse(bg_value)) else {
+                skipped.push(format!("{foreground} on {background}"));
+                continue;
+            };
+
+            let ratio = fg.contrast_ratio(bg);
+            checks.push(ContrastCheck {
+                foreground: foreground.to_string(),
+                background: background.to_string(),
+                foreground_value: fg_value.to_string(),
+                background_value: bg_value.to_string(),
+                ratio: (ratio * 100.0).round() / 100.0,
+                aa: ratio >= AA_RATIO,
+                aaa: ratio >= AAA_RATIO,
+            });
+        }
+
+        Self {
+            name: theme.name.clone(),
+            aa: checks.iter().all(|c| c.aa),
+            aaa: checks.iter().all(|c| c.aaa),
+            checks,
+            skipped,
+        }
+    }
+
+    /// Pairs below AA.
+    pub fn failures(&self) -> impl Iterator<Item = &ContrastCheck> {
+        self.checks.iter().filter(|c| !c.aa)
+    }
+}
+
+/// Themes with a pair below AA, refused under [`ContrastMode::Enforce`].
+#[derive(Debug, thiserror::Error)]
+#[error("themes fail WCAG AA contrast: {}", .0.join("; "))]
+pub struct NonCompliantThemes(pub Vec<String>);
+
+/// `(foreground, background)` color keys to check, in token order.
+fn pairs(theme: &Theme) -> Vec<(&str, &str)> {
+    let colors = &theme.tokens.colors;
+    let mut pairs = Vec::new();
+
+    // `base-content` may come from `contrast`, so look it up rather than iterate for it
+    if theme.tokens.color("base-content").is_some() {
+        pairs.extend(
+            colors
+                .keys()
+                .filter(|key| key.starts_with("base-") && *key != "base-content")
+                .map(|key| ("base-content", key.as_str())),
+        );
+    }
+    pairs.extend(colors.keys().filter_map(|key| {
+        let background = key.strip_suffix("-content")?;
+        (background != "base" && colors.contains_key(background))
+            .then_some((key.as_str(), background))
+    }));
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    #[test]
+    fn checks_content_colors_against_their_backgrounds() {
+        let css = r#"@plugin "daisyui/theme" {
+  name: "mixed";
+  --color-base-100: #ffffff;
+  --color-base-200: #767676;
+  --color-contrast: #000000;
+  --color-primary: #ffffff;
+  --color-primary-content: #767676;
+  --color-secondary: #595959;
+  --color-secondary-content: #ffffff;
+  --color-accent: var(--brand);
+  --color-accent-content: #000000;
+  --color-info-content: #000000;
+}"#;
+
+        let theme = &themes::parse(css, &["base-100".to_string()])[0];
+        let audit = ThemeAudit::new(theme);
+
+        let summary: Vec<String> = audit.checks.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "base-content on base-100 is 21:1",
+                "base-content on base-200 is 4.62:1",
+                "primary-content on primary is 4.54:1",
+                "secondary-content on secondary is 7:1",
+            ]
+        );
+        let flags: Vec<(bool, bool)> = audit.checks.iter().map(|c| (c.aa, c.aaa)).collect();
+        assert_eq!(
+            flags,
+            [(true, true), (true, false), (true, false), (true, true)]
+        );
+        assert_eq!(audit.skipped, ["accent-content on accent"]);
+        assert!(audit.aa && !audit.aaa);
+        assert_eq!(audit.failures().count(), 0);
+    }
+
+    #[test]
+    fn flags_pairs_below_aa() {
+        let css = r#"@plugin "daisyui/theme" {
+  name: "washed-out";
+  --color-base-100: #ffffff;
+  --color-base-content: #777777;
+}"#;
+
+        let audit = ThemeAudit::new(&themes::parse(css, &[])[0]);
+        assert!(!audit.aa && !audit.aaa);
+        let failures: Vec<String> = audit.failures().map(ToString::to_string).collect();
+        assert_eq!(failures, ["base-content on base-100 is 4.48:1"]);
+    }
+}
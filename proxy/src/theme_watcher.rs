@@ -0,0 +1,80 @@
+use notify::{
+    Event, EventKind, RecursiveMode, Watcher,
+    event::{AccessKind, AccessMode},
+};
+use std::{path::Path, sync::Arc, time::Duration};
+use tokio::sync::{mpsc, watch};
+
+use crate::themes::ThemeStore;
+
+/// Editors often save in several steps (truncate, write, rename); wait for them to settle.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Re-parses the themes whenever `themes_css_path` changes, until `shutdown` flips to `true`.
+///
+/// The parent directory is watched rather than the file itself, so a save that replaces
+/// the file (write to temp, then rename) is still picked up.
+pub async fn run(store: Arc<ThemeStore>, mut shutdown: watch::Receiver<bool>) {
+    let path = Path::new(store.path()).to_path_buf();
+    let Some(file_name) = path.file_name().map(ToOwned::to_owned) else {
+        tracing::warn!(path = %path.display(), "Theme path has no file name; not watching");
+        return;
+    };
+    let dir = match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(dir) => dir.to_path_buf(),
+        None => Path::new(".").to_path_buf(),
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        // Skip plain reads, including our own reload of the file
+        Ok(event)
+            if is_write(&event.kind)
+                && event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == Some(&file_name)) =>
+        {
+            let _ = tx.send(());
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "Theme watcher error"),
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to create theme watcher; hot reload disabled");
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        tracing::warn!(dir = %dir.display(), error = %e, "Failed to watch theme directory; hot reload disabled");
+        return;
+    }
+    tracing::info!(path = %path.display(), "Watching themes for changes");
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => if event.is_none() { break },
+            _ = shutdown.changed() => break,
+        }
+
+        // Collapse the burst of events from a single save into one reload
+        tokio::time::sleep(DEBOUNCE).await;
+        while rx.try_recv().is_ok() {}
+
+        if let Err(e) = store.reload() {
+            tracing::warn!(error = %e, "Theme reload failed; keeping previous themes");
+        }
+    }
+
+    tracing::info!("Theme watcher stopped");
+}
+
+fn is_write(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        EventKind::Access(_) => false,
+        _ => true,
+    }
+}
@@ -0,0 +1,512 @@
+//! daisyUI themes parsed from `themes_css_path`, held in memory for `/api/themes`.
+//!
+//! Theme blocks are found with the tokenizer in [`css`], so comments, quoted braces and
+//! nested at-rules inside a block do not cut it short.
+//!
+//! The CSS is parsed once at startup and again whenever `theme_watcher` sees the file
+//! change; readers always get a complete list, never a half-parsed one. Each load is also
+//! contrast-audited (see [`theme_audit`]) and handled per `themes_contrast_mode`.
+
+use anyhow::Context;
+use axum::body::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    color::{Color, ColorFormat},
+    css, http_cache,
+    theme_audit::{ContrastMode, NonCompliantThemes, ThemeAudit},
+};
+
+/// Colors every theme must define unless `themes_required_colors` says otherwise.
+pub const DEFAULT_REQUIRED_COLORS: &[&str] =
+    &["primary", "secondary", "accent", "base-100", "base-content"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Theme {
+    pub name: String,
+    /// Copies of the matching `tokens.colors` entries, empty when the theme omits them.
+    pub primary: String,
+    pub secondary: String,
+    pub accent: String,
+    pub base100: String,
+    pub base_content: String,
+    /// `default: true`, i.e. the theme used when none is selected.
+    pub default: bool,
+    /// `prefersdark: true`, i.e. the theme used for `prefers-color-scheme: dark`.
+    pub prefers_dark: bool,
+    /// The `color-scheme` the theme declares (`light` or `dark`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_scheme: Option<String>,
+    pub tokens: ThemeTokens,
+    /// Color values that could not be parsed; they are passed through unconverted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl Theme {
+    /// A copy with every parseable color written in `format`.
+    pub fn with_color_format(&self, format: ColorFormat) -> Self {
+        let convert = |value: &str| {
+            Color::parse(value)
+                .map(|color| color.format(format))
+                .unwrap_or_else(|_| value.to_string())
+        };
+        let mut theme = self.clone();
+        for value in [
+            &mut theme.primary,
+            &mut theme.secondary,
+            &mut theme.accent,
+            &mut theme.base100,
+            &mut theme.base_content,
+        ] {
+            if !value.is_empty() {
+                *value = convert(value);
+            }
+        }
+        for value in theme.tokens.colors.values_mut() {
+            *value = convert(value);
+        }
+        theme
+    }
+}
+
+/// The theme's design tokens, keyed without their `--color-`, `--radius-` or `--size-` prefix.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThemeTokens {
+    pub colors: BTreeMap<String, String>,
+    pub radius: BTreeMap<String, String>,
+    pub size: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise: Option<String>,
+}
+
+impl ThemeTokens {
+    fn from_block(block: &css::Block) -> Self {
+        let mut tokens = Self::default();
+        // Later declarations win, as in the cascade
+        for css::Declaration { name, value } in &block.declarations {
+            let value = value.clone();
+            if let Some(key) = name.strip_prefix("--color-") {
+                tokens.colors.insert(key.to_string(), value);
+            } else if let Some(key) = name.strip_prefix("--radius-") {
+                tokens.radius.insert(key.to_string(), value);
+            } else if let Some(key) = name.strip_prefix("--size-") {
+                tokens.size.insert(key.to_string(), value);
+            } else {
+                match name.as_str() {
+                    "--border" => tokens.border = Some(value),
+                    "--depth" => tokens.depth = Some(value),
+                    "--noise" => tokens.noise = Some(value),
+                    _ => {}
+                }
+            }
+        }
+        tokens
+    }
+
+    /// Looks up a color, accepting `contrast` for a missing `base-content`.
+    pub fn color(&self, key: &str) -> Option<&str> {
+        self.colors
+            .get(key)
+            .or_else(|| (key == "base-content").then(|| self.colors.get("contrast"))?)
+            .map(String::as_str)
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// Extracts daisyUI theme blocks and their tokens, skipping blocks without a name or
+/// without one of `required_colors`.
+pub fn parse(css: &str, required_colors: &[String]) -> Vec<Theme> {
+    let mut themes: Vec<Theme> = Vec::new();
+    let blocks = css::plugin_blocks(css, "daisyui/theme");
+
+    for (idx, block) in blocks.iter().enumerate() {
+        let Some(name) = block.declaration("name").map(str::to_string) else {
+            warn!(
+                idx = idx + 1,
+                "Theme block missing a `name` entry; skipping"
+            );
+            continue;
+        };
+        debug!(theme = %name, declarations = block.declarations.len(), "Found theme block");
+
+        let tokens = ThemeTokens::from_block(block);
+        let missing: Vec<&str> = required_colors
+            .iter()
+            .map(String::as_str)
+            .filter(|key| tokens.color(key).is_none())
+            .collect();
+        if !missing.is_empty() {
+            warn!(theme = %name, missing = ?missing, "Skipping theme due to missing colors");
+            continue;
+        }
+
+        let warnings: Vec<String> = tokens
+            .colors
+            .iter()
+            .filter_map(|(key, value)| {
+                Color::parse(value)
+                    .err()
+                    .map(|e| format!("--color-{key}: {e}"))
+            })
+            .collect();
+        if !warnings.is_empty() {
+            warn!(theme = %name, warnings = ?warnings, "Theme has unparseable colors");
+        }
+
+        let color = |key: &str| tokens.color(key).unwrap_or_default().to_string();
+        let flag = |key: &str| block.declaration(key) == Some("true");
+        themes.push(Theme {
+            primary: color("primary"),
+            secondary: color("secondary"),
+            accent: color("accent"),
+            base100: color("base-100"),
+            base_content: color("base-content"),
+            default: flag("default"),
+            prefers_dark: flag("prefersdark"),
+            color_scheme: block
+                .declaration("color-scheme")
+                .filter(|v| !v.is_empty())
+                .map(str::to_string),
+            name,
+            tokens,
+            warnings,
+        });
+    }
+
+    info!(
+        total_blocks = blocks.len(),
+        total_added = themes.len(),
+        "Theme parsing complete"
+    );
+
+    themes
+}
+
+/// A serialized theme response and its ETag.
+pub struct RenderedThemes {
+    pub body: Bytes,
+    pub etag: String,
+}
+
+impl RenderedThemes {
+    fn new<T: Serialize + ?Sized>(value: &T) -> Self {
+        let body = Bytes::from(serde_json::to_vec(value).expect("Theme always serializes"));
+        let etag = http_cache::strong_etag(&[&body]);
+        Self { body, etag }
+    }
+}
+
+/// A parsed theme list, pre-rendered verbatim and in every [`ColorFormat`], with its audit.
+pub struct ThemeSet {
+    pub themes: Vec<Theme>,
+    pub audits: Vec<ThemeAudit>,
+    verbatim: RenderedThemes,
+    converted: Vec<(ColorFormat, RenderedThemes)>,
+    audit: RenderedThemes,
+}
+
+impl ThemeSet {
+    pub fn new(themes: Vec<Theme>) -> Self {
+        let converted = ColorFormat::ALL
+            .into_iter()
+            .map(|format| {
+                let themes: Vec<Theme> =
+                    themes.iter().map(|t| t.with_color_format(format)).collect();
+                (format, RenderedThemes::new(&themes))
+            })
+            .collect();
+        let audits: Vec<ThemeAudit> = themes.iter().map(ThemeAudit::new).collect();
+        Self {
+            verbatim: RenderedThemes::new(&themes),
+            converted,
+            audit: RenderedThemes::new(&audits),
+            themes,
+            audits,
+        }
+    }
+
+    /// The list with colors as written in the CSS, or converted to `format`.
+    pub fn rendered(&self, format: Option<ColorFormat>) -> &RenderedThemes {
+        format
+            .and_then(|format| self.converted.iter().find(|(f, _)| *f == format))
+            .map_or(&self.verbatim, |(_, rendered)| rendered)
+    }
+
+    /// The contrast audit of every theme.
+    pub fn audit(&self) -> &RenderedThemes {
+        &self.audit
+    }
+}
+
+/// Theme names that appear, disappear or change colors between two lists.
+#[derive(Debug, Default, PartialEq)]
+pub struct ThemeDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ThemeDiff {
+    pub fn between(before: &[Theme], after: &[Theme]) -> Self {
+        let before: BTreeMap<&str, &Theme> = before.iter().map(|t| (t.name.as_str(), t)).collect();
+        let after: BTreeMap<&str, &Theme> = after.iter().map(|t| (t.name.as_str(), t)).collect();
+
+        let mut diff = Self::default();
+        for (name, theme) in &after {
+            match before.get(name) {
+                None => diff.added.push(name.to_string()),
+                Some(old) if old != theme => diff.changed.push(name.to_string()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .keys()
+            .filter(|name| !after.contains_key(*name))
+            .map(|name| name.to_string())
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The current theme list, swapped as a whole on reload.
+pub struct ThemeStore {
+    path: String,
+    required_colors: Vec<String>,
+    contrast_mode: ContrastMode,
+    current: RwLock<Option<Arc<ThemeSet>>>,
+}
+
+impl ThemeStore {
+    pub fn new(path: &str, required_colors: &[String], contrast_mode: ContrastMode) -> Self {
+        Self {
+            path: path.to_string(),
+            required_colors: required_colors.to_vec(),
+            contrast_mode,
+            current: RwLock::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The last successfully loaded list, or `None` if the file has never been readable.
+    pub fn current(&self) -> Option<Arc<ThemeSet>> {
+        self.current
+            .read()
+            .expect("theme store lock poisoned")
+            .clone()
+    }
+
+    /// Re-reads and parses the CSS file, then swaps in the result and logs what changed.
+    ///
+    /// On a read error, or a [`NonCompliantThemes`] error under [`ContrastMode::Enforce`],
+    /// the previous list stays in place.
+    pub fn reload(&self) -> anyhow::Result<ThemeDiff> {
+        let css = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read CSS file {}", self.path))?;
+        let next = Arc::new(ThemeSet::new(parse(&css, &self.required_colors)));
+        self.check_contrast(&next)?;
+
+        let previous = {
+            let mut guard = self.current.write().expect("theme store lock poisoned");
+            guard.replace(Arc::clone(&next))
+        };
+        let diff = ThemeDiff::between(
+            previous.as_ref().map_or(&[][..], |p| &p.themes),
+            &next.themes,
+        );
+
+        if previous.is_none() {
+            info!(path = %self.path, themes = next.themes.len(), "Loaded themes");
+        } else if diff.is_empty() {
+            debug!(path = %self.path, "Themes unchanged after reload");
+        } else {
+            info!(
+                path = %self.path,
+                added = ?diff.added,
+                removed = ?diff.removed,
+                changed = ?diff.changed,
+                themes = next.themes.len(),
+                "Themes reloaded"
+            );
+        }
+
+        Ok(diff)
+    }
+
+    fn check_contrast(&self, set: &ThemeSet) -> Result<(), NonCompliantThemes> {
+        let failures: Vec<String> = set
+            .audits
+            .iter()
+            .flat_map(|audit| {
+                audit
+                    .failures()
+                    .map(move |check| format!("{}: {check}", audit.name))
+            })
+            .collect();
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        match self.contrast_mode {
+            ContrastMode::Off => Ok(()),
+            ContrastMode::Warn => {
+                for failure in &failures {
+                    warn!(path = %self.path, failure = %failure, "Theme fails WCAG AA contrast");
+                }
+                Ok(())
+            }
+            ContrastMode::Enforce => Err(NonCompliantThemes(failures)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn required() -> Vec<String> {
+        DEFAULT_REQUIRED_COLORS
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    fn block(name: &str, primary: &str) -> String {
+        format!(
+            r#"@plugin "daisyui/theme" {{
+  name: "{name}";
+  --color-primary: {primary};
+  --color-secondary: "oklch(70% 0.2 200)";
+  --color-accent: #ff00ff;
+  --color-base-100: white;
+  --color-contrast: black;
+}}
+"#
+        )
+    }
+
+    #[test]
+    fn parses_blocks_and_skips_incomplete_ones() {
+        let css = format!(
+            "{}{}@plugin \"daisyui/theme\" {{ name: \"broken\"; --color-primary: red; }}",
+            block("light", "red"),
+            block("dark", "'blue'"),
+        );
+
+        let themes = parse(&css, &required());
+        assert_eq!(themes.len(), 2);
+        assert_eq!(themes[0].name, "light");
+        assert_eq!(themes[0].secondary, "oklch(70% 0.2 200)");
+        assert_eq!(themes[0].base_content, "black");
+        assert_eq!(themes[1].primary, "blue");
+
+        // Only the configured colors are required
+        let themes = parse(&css, &["primary".to_string()]);
+        assert_eq!(themes.len(), 3);
+        assert_eq!(themes[2].accent, "");
+    }
+
+    #[test]
+    fn collects_the_token_map() {
+        let css = r#"@plugin "daisyui/theme" {
+  name: "night";
+  default: false;
+  prefersdark: true;
+  color-scheme: "dark";
+  --color-primary: #38bdf8;
+  --color-primary-content: #010d15;
+  --radius-box: 1rem;
+  --radius-field: 0.5rem;
+  --size-selector: 0.25rem;
+  --border: 1px;
+  --depth: 1;
+  --noise: 0;
+  --unrelated: x;
+}"#;
+
+        let themes = parse(css, &["primary".to_string()]);
+        let night = &themes[0];
+        assert!(night.prefers_dark && !night.default);
+        assert_eq!(night.color_scheme.as_deref(), Some("dark"));
+        assert_eq!(
+            night.tokens,
+            ThemeTokens {
+                colors: [
+                    ("primary".to_string(), "#38bdf8".to_string()),
+                    ("primary-content".to_string(), "#010d15".to_string()),
+                ]
+                .into(),
+                radius: [
+                    ("box".to_string(), "1rem".to_string()),
+                    ("field".to_string(), "0.5rem".to_string()),
+                ]
+                .into(),
+                size: [("selector".to_string(), "0.25rem".to_string())].into(),
+                border: Some("1px".into()),
+                depth: Some("1".into()),
+                noise: Some("0".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn converts_colors_and_reports_unparseable_ones() {
+        let css = r#"@plugin "daisyui/theme" {
+  name: "mixed";
+  --color-primary: "oklch(62.8% 0.2577 29.23)";
+  --color-secondary: rgb(0 0 255);
+  --color-neutral: var(--brand);
+}"#;
+
+        let theme = &parse(css, &["primary".to_string()])[0];
+        assert_eq!(
+            theme.warnings,
+            ["--color-neutral: unrecognized color \"var(--brand)\""]
+        );
+
+        let hex = theme.with_color_format(ColorFormat::Hex);
+        assert_eq!(hex.primary, "#ff0000");
+        assert_eq!(hex.tokens.colors["secondary"], "#0000ff");
+        assert_eq!(hex.tokens.colors["neutral"], "var(--brand)");
+        assert_eq!(hex.accent, "");
+    }
+
+    #[test]
+    fn diffs_themes_by_name() {
+        let before = parse(
+            &format!("{}{}", block("light", "red"), block("dark", "red")),
+            &required(),
+        );
+        let after = parse(
+            &format!("{}{}", block("light", "green"), block("cupcake", "red")),
+            &required(),
+        );
+
+        assert_eq!(
+            ThemeDiff::between(&before, &after),
+            ThemeDiff {
+                added: vec!["cupcake".into()],
+                removed: vec!["dark".into()],
+                changed: vec!["light".into()],
+            }
+        );
+    }
+}
@@ -1,11 +1,15 @@
 //! Integration test for the proxy binary.
 //!
 //! Spawns a dummy upstream SSR server (Axum) and then launches the compiled
-//! proxy binary as a child process, pointing it at the upstream via env vars.
+//! proxy binary as a child process, pointing it at the upstream via its config file.
 //! Verifies:
 //!   - Proxy responds with upstream body
 //!   - Adds x-proxy header
 //!   - Metrics endpoint exposes counters for requests
+//!   - /api/country falls back to the on-disk snapshot when the country API is down
+//!   - `country_source: Some(Embedded)` serves the compiled-in fixture offline
+//!   - /api/themes picks up edits to the theme CSS without a restart, and /api/themes/audit
+//!     reports their contrast
 //!
 //! This test assumes the proxy binary has already been built by `cargo test`.
 //! Paths checked:
@@ -14,23 +18,23 @@
 //!
 //! If running tests with `--release`, the release path will be used.
 //!
-//! NOTE: The proxy will attempt to serve static assets from `dist/client`.
+//! NOTE: The proxy loads `proxy/proxy.ron` relative to its working directory and requires
+//! an existing asset directory, so each test writes both under a throwaway directory in the
+//! system temp dir and starts the proxy there. [`TestProxy`] kills the proxy and removes the
+//! directory when the test ends, pass or fail.
 //! For this integration test, we only exercise the reverse proxy fallback.
 //!
-//! Environment variables consumed by proxy:
-//!   PROXY_PORT, SSR_UPSTREAM_HOST, SSR_UPSTREAM_PORT, METRICS_PORT
+//! The test chooses random high ports via binding to port 0 and writes them into the config.
 //!
-//! The test chooses random high ports via binding to port 0.
-//!
-//! Uses only existing dependencies (tokio, axum, hyper) to avoid adding
-//! extra dev-dependencies.
+//! Uses existing dependencies (tokio, axum, hyper) plus `http-body-util`
+//! for collecting response bodies.
 
-use axum::{Router, body::Body, routing::get};
+use axum::{Router, body::Body, extract::Path as UrlPath, routing::get};
 use http_body_util::BodyExt;
 use hyper_util::client::legacy::{Client, connect::HttpConnector};
 use std::{
     env, fs,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
     time::Duration,
 };
@@ -38,10 +42,16 @@ use tokio::net::TcpListener;
 use tokio::time::sleep;
 
 /// Build an ephemeral upstream axum server returning fixed text for any GET /test route.
+/// It also stands in for the flag CDN under /flags.
 async fn spawn_upstream() -> (u16, tokio::task::JoinHandle<()>) {
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
     let port = listener.local_addr().unwrap().port();
-    let app = Router::new().route("/test", get(|| async { "UPSTREAM OK" }));
+    let app = Router::new()
+        .route("/test", get(|| async { "UPSTREAM OK" }))
+        .route(
+            "/flags/{file}",
+            get(|UrlPath(file): UrlPath<String>| async move { format!("FLAG {file}") }),
+        );
 
     let handle = tokio::spawn(async move {
         if let Err(e) = axum::serve(listener, app.into_make_service()).await {
@@ -53,7 +63,7 @@ async fn spawn_upstream() -> (u16, tokio::task::JoinHandle<()>) {
 }
 
 /// Find the proxy binary path (debug first, then release).
-fn find_proxy_binary() -> Option<std::path::PathBuf> {
+fn find_proxy_binary() -> Option<PathBuf> {
     let manifest_dir = env!("CARGO_MANIFEST_DIR");
     let debug_path = Path::new(manifest_dir)
         .join("target")
@@ -72,8 +82,43 @@ fn find_proxy_binary() -> Option<std::path::PathBuf> {
     None
 }
 
-/// Spawn proxy child process configured to point at our dummy upstream.
-fn spawn_proxy(upstream_port: u16, proxy_port: u16) -> Child {
+/// Simplified country payload used as the snapshot fixture.
+const SNAPSHOT_JSON: &str = r#"[{"code":"FR","cca3":"FRA","name":"France","region":"Europe","population":67391582,"flag":""}]"#;
+
+/// Write a minimal `proxy/proxy.ron` into `dir`, next to the files [`TestProxy::start`]
+/// creates. The country API URL points at a closed port, so every upstream country fetch
+/// fails unless `extra` selects another `country_source`.
+fn write_test_config(dir: &Path, upstream_port: u16, proxy_port: u16, extra: &str) {
+    let config_path = dir.join("proxy").join("proxy.ron");
+    fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+    fs::write(
+        &config_path,
+        format!(
+            r#"(
+    country_api_url: "http://127.0.0.1:9/unused",
+    proxy_port: {proxy_port},
+    upstream_host: "127.0.0.1",
+    upstream_port: {upstream_port},
+    asset_dir: "{}",
+    rate_limit_per_second: 100,
+    rate_limit_burst_size: 100,
+    country_snapshot_path: Some("{}"),
+    flag_cache_dir: "{}",
+{extra}
+)"#,
+            dir.join("client").display(),
+            dir.join("countries.snapshot.json").display(),
+            dir.join("flags").display()
+        ),
+    )
+    .unwrap();
+}
+
+/// Spawn the proxy with `dir` as its working directory.
+///
+/// Its output is discarded: nothing reads the pipes, so a chatty proxy would otherwise
+/// block once they fill up.
+fn spawn_proxy(dir: &Path) -> Child {
     let binary =
         find_proxy_binary().unwrap_or_else(|| panic!("Proxy binary not found. Build failed?"));
 
@@ -87,15 +132,105 @@ fn spawn_proxy(upstream_port: u16, proxy_port: u16) -> Child {
     }
 
     let mut cmd = Command::new(&binary);
-    cmd.env("PROXY_PORT", proxy_port.to_string())
-        .env("SSR_UPSTREAM_HOST", "127.0.0.1")
-        .env("SSR_UPSTREAM_PORT", upstream_port.to_string())
+    cmd.current_dir(dir)
         .env("LOG_FORMAT", "json") // exercise JSON logging path
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    cmd.spawn().expect("Failed to spawn proxy process")
+}
+
+/// A proxy child process, its dummy upstream and its temp dir.
+///
+/// Dropping it kills the proxy and removes the dir, so nothing leaks when an assertion fails.
+struct TestProxy {
+    child: Child,
+    dir: PathBuf,
+    port: u16,
+    _upstream: tokio::task::JoinHandle<()>,
+}
+
+impl TestProxy {
+    /// Start a dummy upstream and a proxy in a fresh temp dir named after `name`, then wait
+    /// until the proxy answers.
+    ///
+    /// The dir holds an empty asset dir and a one-country snapshot. `setup` receives the dir
+    /// and the upstream port before the proxy starts; it may add or replace files and returns
+    /// extra `proxy.ron` lines.
+    async fn start(name: &str, setup: impl FnOnce(&Path, u16) -> String) -> Self {
+        let (upstream_port, upstream) = spawn_upstream().await;
+
+        // Reserve proxy port by binding then letting it go (to reduce collision risk)
+        let proxy_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = proxy_listener.local_addr().unwrap().port();
+        drop(proxy_listener);
+
+        let dir = env::temp_dir().join(format!("proxy-it-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("client")).unwrap();
+        fs::write(dir.join("countries.snapshot.json"), SNAPSHOT_JSON).unwrap();
+
+        let extra = setup(&dir, upstream_port);
+        write_test_config(&dir, upstream_port, port, &extra);
+        let proxy = Self {
+            child: spawn_proxy(&dir),
+            dir,
+            port,
+            _upstream: upstream,
+        };
+
+        wait_for_get_ok(&proxy.url("/isHealthy"), Duration::from_secs(8))
+            .await
+            .expect("Proxy did not become ready");
+        proxy
+    }
 
-    let child = cmd.spawn().expect("Failed to spawn proxy process");
-    child
+    fn url(&self, path: &str) -> String {
+        format!("http://127.0.0.1:{}{path}", self.port)
+    }
+
+    /// GET `path` once, returning the status, the named header (if any) and the body text.
+    async fn get(&self, path: &str, header: &str) -> (u16, Option<String>, String) {
+        get_with_header(&self.url(path), header).await
+    }
+
+    /// GET `path` and parse the JSON body, asserting a 200 response.
+    async fn get_json(&self, path: &str) -> serde_json::Value {
+        let (status, _, body) = self.get(path, "x-data-source").await;
+        assert_eq!(status, 200, "GET {path}: {body}");
+        serde_json::from_str(&body).unwrap_or_else(|e| panic!("GET {path}: {e}: {body}"))
+    }
+}
+
+impl Drop for TestProxy {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Start a proxy on `country_source: Some(Embedded)` (with flags fetched from the dummy
+/// upstream) and wait until the fixture has replaced the one-country snapshot.
+async fn embedded_proxy(name: &str) -> TestProxy {
+    let proxy = TestProxy::start(name, |_, upstream_port| {
+        format!(
+            r#"    country_source: Some(Embedded),
+    flag_png_url: "http://127.0.0.1:{upstream_port}/flags/{{code}}.png","#
+        )
+    })
+    .await;
+
+    let mut body = String::new();
+    for _ in 0..40 {
+        body = proxy.get("/api/country?q=japan", "x-data-source").await.2;
+        if body.contains("\"Japan\"") {
+            break;
+        }
+        sleep(Duration::from_millis(250)).await;
+    }
+    assert!(body.contains("\"Japan\""), "Unexpected body: {body}");
+    proxy
 }
 
 /// Poll an HTTP GET until success or timeout.
@@ -105,19 +240,16 @@ async fn wait_for_get_ok(url: &str, timeout: Duration) -> Result<Vec<u8>, String
     let start = std::time::Instant::now();
 
     loop {
-        match client.get(url.parse().unwrap()).await {
-            Ok(resp) => {
-                if resp.status().is_success() {
-                    let body_bytes = resp
-                        .into_body()
-                        .collect()
-                        .await
-                        .map_err(|e| format!("Body read error: {e}"))?
-                        .to_bytes();
-                    return Ok(body_bytes.to_vec());
-                }
-            }
-            Err(_) => {}
+        if let Ok(resp) = client.get(url.parse().unwrap()).await
+            && resp.status().is_success()
+        {
+            let body_bytes = resp
+                .into_body()
+                .collect()
+                .await
+                .map_err(|e| format!("Body read error: {e}"))?
+                .to_bytes();
+            return Ok(body_bytes.to_vec());
         }
         if start.elapsed() > timeout {
             return Err(format!("Timeout waiting for {url}"));
@@ -126,6 +258,20 @@ async fn wait_for_get_ok(url: &str, timeout: Duration) -> Result<Vec<u8>, String
     }
 }
 
+/// GET a URL once, returning the status, the named header (if any) and the body text.
+async fn get_with_header(url: &str, name: &str) -> (u16, Option<String>, String) {
+    let client: Client<_, Body> =
+        Client::builder(hyper_util::rt::TokioExecutor::new()).build(HttpConnector::new());
+    let resp = client.get(url.parse().unwrap()).await.unwrap();
+    let status = resp.status().as_u16();
+    let header = resp
+        .headers()
+        .get(name)
+        .map(|v| v.to_str().unwrap().to_string());
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (status, header, String::from_utf8_lossy(&bytes).into_owned())
+}
+
 /// Read metrics endpoint text.
 async fn fetch_metrics(url: &str) -> Result<String, String> {
     let client: Client<_, Body> =
@@ -148,19 +294,10 @@ async fn fetch_metrics(url: &str) -> Result<String, String> {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_proxy_end_to_end() {
-    // Spawn upstream
-    let (upstream_port, _upstream_handle) = spawn_upstream().await;
-
-    // Reserve proxy port by binding then letting it go (to reduce collision risk)
-    let proxy_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let proxy_port = proxy_listener.local_addr().unwrap().port();
-    drop(proxy_listener);
-
-    // Spawn proxy
-    let mut proxy_child = spawn_proxy(upstream_port, proxy_port);
+    let proxy = TestProxy::start("end-to-end", |_, _| String::new()).await;
 
-    // Wait for proxy to respond
-    let proxy_url = format!("http://127.0.0.1:{proxy_port}/test");
+    // The proxy forwards to the upstream
+    let proxy_url = proxy.url("/test");
     let body = wait_for_get_ok(&proxy_url, Duration::from_secs(8))
         .await
         .expect("Proxy did not become ready");
@@ -172,8 +309,7 @@ async fn test_proxy_end_to_end() {
         .expect("Second request failed");
 
     // Fetch metrics
-    let metrics_url = format!("http://127.0.0.1:{proxy_port}/metrics");
-    let metrics_output = fetch_metrics(&metrics_url)
+    let metrics_output = fetch_metrics(&proxy.url("/api/metrics"))
         .await
         .expect("metrics fetch failed");
 
@@ -188,10 +324,318 @@ async fn test_proxy_end_to_end() {
         metrics_output.contains("proxy_upstream_latency_seconds"),
         "Expected latency histogram in metrics output"
     );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_country_snapshot_fallback() {
+    let proxy = TestProxy::start("snapshot", |_, _| String::new()).await;
+
+    // The country API is unreachable, so the snapshot must be served
+    let (status, source, body) = proxy.get("/api/country", "x-data-source").await;
+    assert_eq!(status, 200);
+    assert_eq!(source.as_deref(), Some("snapshot"));
+    assert!(body.contains("\"France\""), "Unexpected body: {body}");
+
+    // Single-country lookups share the same data and accept cca2 or cca3 codes
+    let (status, _, body) = proxy.get("/api/country/fra", "x-data-source").await;
+    assert_eq!(status, 200);
+    assert!(body.contains("\"FR\""), "Unexpected body: {body}");
+
+    let (status, _, _) = proxy.get("/api/country/zz", "x-data-source").await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embedded_fields() {
+    let proxy = embedded_proxy("embedded-fields").await;
+
+    let (status, source, body) = proxy.get("/api/country/jpn", "x-data-source").await;
+    assert_eq!(status, 200);
+    assert_eq!(source.as_deref(), Some("upstream"));
+    // Detail fields are only sent when requested
+    let japan: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(japan["cca3"], "JPN");
+    for field in [
+        "capital",
+        "languages",
+        "currencies",
+        "borders",
+        "translations",
+    ] {
+        assert!(japan.get(field).is_none(), "Unexpected {field}: {body}");
+    }
+
+    // Every country endpoint reports the response shape in the X-Schema-Version header and
+    // on each record, so saved and exported bodies still carry it
+    for path in [
+        "/api/country",
+        "/api/country?format=ndjson",
+        "/api/country/jp?fields=name",
+        "/api/country/pt/neighbors",
+        "/api/country/search?q=japan",
+        "/api/route?from=PT&to=ES",
+    ] {
+        let (status, version, body) = proxy.get(path, "x-schema-version").await;
+        assert_eq!(status, 200, "GET {path}");
+        assert_eq!(version.as_deref(), Some("9"), "GET {path}");
+        assert!(body.contains(r#""schemaVersion":9"#), "GET {path}: {body}");
+    }
+    let (_, version, body) = proxy
+        .get("/api/country?format=csv&fields=code", "x-schema-version")
+        .await;
+    assert_eq!(version.as_deref(), Some("9"));
+    assert!(
+        body.starts_with("code,schemaVersion\r\nAX,9\r\n"),
+        "Unexpected body: {body}"
+    );
+
+    // Field selection keeps only allowlisted fields and rejects unknown ones
+    let japan = proxy
+        .get_json("/api/country/jp?fields=name,capital,currencies")
+        .await;
+    assert_eq!(
+        japan,
+        serde_json::json!({
+            "name": "Japan",
+            "capital": ["Tokyo"],
+            "currencies": [{"code": "JPY", "name": "Japanese yen", "symbol": "¥"}],
+            "schemaVersion": 9,
+        })
+    );
+
+    let (status, _, body) = proxy
+        .get("/api/country?fields=name,secret", "x-data-source")
+        .await;
+    assert_eq!(status, 400);
+    assert!(body.contains("\"secret\""), "Unexpected body: {body}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embedded_neighbors_and_route() {
+    let proxy = embedded_proxy("embedded-route").await;
+
+    // Border graph: neighbours and shortest land route
+    let (status, _, body) = proxy
+        .get("/api/country/pt/neighbors?fields=code", "x-data-source")
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(body, r#"[{"code":"ES","schemaVersion":9}]"#);
+
+    let route = proxy.get_json("/api/route?from=PT&to=de").await;
+    assert_eq!(route["hops"], 3, "Unexpected body: {route}");
+    let path: Vec<&str> = route["path"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["code"].as_str().unwrap())
+        .collect();
+    assert_eq!(path, ["PT", "ES", "FR", "DE"]);
+
+    let (status, _, _) = proxy.get("/api/route?from=FR&to=JP", "x-data-source").await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embedded_locale() {
+    let proxy = embedded_proxy("embedded-locale").await;
+
+    // Localized names, sorted with the language's collation
+    let (status, content_language, body) = proxy
+        .get(
+            "/api/country?lang=de&region=Europe&fields=code,name",
+            "content-language",
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(content_language.as_deref(), Some("de"));
+    let names: Vec<String> = serde_json::from_str::<Vec<serde_json::Value>>(&body)
+        .unwrap()
+        .iter()
+        .map(|c| c["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        names[..5],
+        ["Åland", "Andorra", "Belgien", "Deutschland", "Frankreich"]
+    );
+    assert_eq!(names[9..11], ["Österreich", "Portugal"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embedded_exports() {
+    let proxy = embedded_proxy("embedded-exports").await;
+
+    // Filtered CSV export via the format parameter
+    let (status, content_type, body) = proxy
+        .get("/api/country?region=Oceania&format=csv", "content-type")
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(content_type.as_deref(), Some("text/csv; charset=utf-8"));
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines.len(), 3, "Unexpected body: {body}");
+    assert!(lines[0].starts_with("code,cca3,name,"));
+    assert!(lines[1].starts_with("AU,AUS,Australia,Oceania,"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embedded_search() {
+    let proxy = embedded_proxy("embedded-search").await;
+
+    // Search folds diacritics, matches alternate names and tolerates typos
+    let results = proxy.get_json("/api/country/search?q=Cote").await;
+    assert_eq!(
+        results[0]["country"]["code"], "CI",
+        "Unexpected body: {results}"
+    );
+
+    let results = proxy.get_json("/api/country/search?q=Brazl").await;
+    assert_eq!(
+        results[0]["country"]["code"], "BR",
+        "Unexpected body: {results}"
+    );
+    assert_eq!(results[0]["match"], "fuzzy");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embedded_regions() {
+    let proxy = embedded_proxy("embedded-regions").await;
+
+    // Region aggregates are computed from the same cached data
+    let regions = proxy.get_json("/api/regions").await;
+    let asia = regions
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["name"] == "Asia")
+        .unwrap_or_else(|| panic!("Asia missing: {regions}"));
+    assert_eq!(asia["countryCount"], 2);
+    assert_eq!(asia["largestCountry"]["code"], "IN");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embedded_changes() {
+    let proxy = embedded_proxy("embedded-changes").await;
+
+    // The pre-warm was diffed against the one-country snapshot it replaced
+    let changes = proxy.get_json("/api/country/changes").await;
+    assert_eq!(
+        changes.as_array().unwrap().len(),
+        1,
+        "Unexpected body: {changes}"
+    );
+    assert!(changes[0]["removed"].as_array().unwrap().is_empty());
+    assert!(
+        changes[0]["added"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|c| c["code"] == "JP")
+    );
+    assert_eq!(changes[0]["changed"][0]["code"], "FR");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embedded_flags() {
+    let proxy = embedded_proxy("embedded-flags").await;
+
+    // Flags are rewritten to the proxy, fetched once and then served from disk
+    let (_, _, body) = proxy
+        .get("/api/country/jp?fields=flag", "x-data-source")
+        .await;
+    assert_eq!(body, r#"{"flag":"/api/flags/jp.png","schemaVersion":9}"#);
+
+    let (status, content_type, body) = proxy.get("/api/flags/jp.png", "content-type").await;
+    assert_eq!(status, 200);
+    assert_eq!(content_type.as_deref(), Some("image/png"));
+    assert_eq!(body, "FLAG jp.png");
+    let cached = proxy.dir.join("flags").join("jp.png");
+    assert_eq!(fs::read_to_string(cached).unwrap(), "FLAG jp.png");
+
+    let (status, cache_control, _) = proxy.get("/api/flags/jp.png", "cache-control").await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        cache_control.as_deref(),
+        Some("public, max-age=31536000, immutable")
+    );
+
+    let (status, _, _) = proxy.get("/api/flags/zz.png", "x-data-source").await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_cached_flags_without_country_data() {
+    // Neither the country API nor the snapshot is usable, but one flag is already on disk
+    let proxy = TestProxy::start("offline-flags", |dir, _| {
+        fs::write(dir.join("countries.snapshot.json"), "not json").unwrap();
+        fs::create_dir_all(dir.join("flags")).unwrap();
+        fs::write(dir.join("flags").join("fr.png"), "CACHED fr.png").unwrap();
+        String::new()
+    })
+    .await;
+
+    let (status, _, body) = proxy.get("/api/flags/fr.png", "etag").await;
+    assert_eq!(status, 200);
+    assert_eq!(body, "CACHED fr.png");
+
+    // A miss still needs the country data to validate the code
+    let (status, _, _) = proxy.get("/api/flags/de.png", "etag").await;
+    assert_eq!(status, 500);
+}
+
+/// daisyUI theme block with every color `/api/themes` requires.
+fn theme_css(name: &str) -> String {
+    format!(
+        r#"@plugin "daisyui/theme" {{
+  name: "{name}";
+  --color-primary: red;
+  --color-secondary: green;
+  --color-accent: blue;
+  --color-base-100: white;
+  --color-base-content: black;
+}}
+"#
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_themes_hot_reload() {
+    let proxy = TestProxy::start("themes", |dir, _| {
+        let css_path = dir.join("styles.css");
+        fs::write(&css_path, theme_css("light")).unwrap();
+        format!("    themes_css_path: \"{}\",", css_path.display())
+    })
+    .await;
+
+    let (status, _, body) = proxy.get("/api/themes", "etag").await;
+    assert_eq!(status, 200);
+    assert!(body.contains("\"light\""), "Unexpected body: {body}");
+
+    // Editing the file swaps in the new list without a restart
+    fs::write(
+        proxy.dir.join("styles.css"),
+        format!("{}{}", theme_css("light"), theme_css("dark")),
+    )
+    .unwrap();
+    let mut body = String::new();
+    for _ in 0..40 {
+        body = proxy.get("/api/themes", "etag").await.2;
+        if body.contains("\"dark\"") {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(body.contains("\"dark\""), "Theme was not reloaded: {body}");
+
+    // Named CSS colors are converted on request
+    let themes = proxy.get_json("/api/themes?colorFormat=hex").await;
+    assert_eq!(themes[0]["primary"], "#ff0000", "Unexpected body: {themes}");
+    assert_eq!(themes[0]["tokens"]["colors"]["base-100"], "#ffffff");
 
-    // Cleanup
-    let _ = proxy_child.kill();
-    let _ = proxy_child.wait();
+    // Each theme's text colors are audited against their backgrounds
+    let audits = proxy.get_json("/api/themes/audit").await;
+    assert_eq!(audits[0]["name"], "light", "Unexpected body: {audits}");
+    assert_eq!(audits[0]["aa"], true);
+    assert_eq!(audits[0]["checks"][0]["background"], "base-100");
+    assert_eq!(audits[0]["checks"][0]["ratio"], 21.0);
 }
 
 /// Additional test: ensure missing binary path reports clear panic (skipped if binary exists).
@@ -7,6 +7,8 @@ export type Country = {
 	flagEmoji: string
 	flagUrl?: string
 	population: number
+	/** Version of this record's shape, set by the proxy */
+	schemaVersion?: number
 }
 
 export type SortKey = 'name' | 'population'
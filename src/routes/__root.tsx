@@ -39,7 +39,8 @@ export const Route = createRootRouteWithContext<MyRouterContext>()({
 					"script-src 'self' 'unsafe-inline' 'unsafe-eval'", // unsafe-eval needed for dev tools
 					"style-src 'self' 'unsafe-inline' https://fonts.googleapis.com",
 					"font-src 'self' https://fonts.gstatic.com",
-					"img-src 'self' data: https:",
+					// flags come from the proxy (/api/flags), emoji from Twemoji (src/client/emoji.ts)
+					"img-src 'self' data: https://twemoji.maxcdn.com",
 					"connect-src 'self' https://restcountries.com",
 					"frame-src 'none'",
 					"object-src 'none'",
@@ -1,3 +1,16 @@
+/**
+ * Design tokens of a daisyUI theme, keyed without their `--color-`, `--radius-` or
+ * `--size-` prefix.
+ */
+export interface ThemeTokens {
+	colors: Record<string, string>
+	radius: Record<string, string>
+	size: Record<string, string>
+	border?: string
+	depth?: string
+	noise?: string
+}
+
 export interface Theme {
 	name: string
 	primary: string
@@ -5,4 +18,12 @@ export interface Theme {
 	accent: string
 	base100: string
 	baseContent: string
+	/** Used when no theme has been selected */
+	default: boolean
+	/** Used for `prefers-color-scheme: dark` */
+	prefersDark: boolean
+	colorScheme?: 'light' | 'dark' | string
+	tokens: ThemeTokens
+	/** Color values the proxy could not parse (left unconverted) */
+	warnings?: string[]
 }